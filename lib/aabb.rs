@@ -0,0 +1,21 @@
+use glam::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub const fn new(min: Vec3, max: Vec3) -> Aabb {
+        Aabb { min, max }
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        return Aabb::new(self.min.min(other.min), self.max.max(other.max));
+    }
+
+    pub fn size(&self) -> Vec3 {
+        return self.max - self.min;
+    }
+}
@@ -1,8 +1,70 @@
+use std::fmt;
+use std::sync::Arc;
+
 use glam::*;
+use rand::Rng;
 
 use crate::ray::Ray;
-use crate::util::rand_in_unit_disc;
+use crate::util::rand_in_unit_disc_with;
+
+// shape of the lens aperture, which determines the shape of out-of-focus highlights
+#[derive(Clone)]
+pub enum ApertureShape {
+    Circle,
+    Polygon { blades: u32 },
+    // returns a point within the unit disc
+    Custom(Arc<dyn Fn() -> Vec2 + Send + Sync>),
+}
+
+impl ApertureShape {
+    // samples a point on the aperture, scaled to fit the unit disc
+    pub fn sample(&self) -> Vec2 {
+        return self.sample_with(&mut rand::thread_rng());
+    }
+
+    // like `sample`, drawing from `rng`; custom shapes bring their own randomness and ignore it
+    pub fn sample_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec2 {
+        match self {
+            ApertureShape::Circle => rand_in_unit_disc_with(rng),
+            ApertureShape::Polygon { blades } => rand_in_unit_polygon(*blades, rng),
+            ApertureShape::Custom(f) => f(),
+        }
+    }
+}
+
+impl fmt::Debug for ApertureShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApertureShape::Circle => write!(f, "Circle"),
+            ApertureShape::Polygon { blades } => f.debug_struct("Polygon").field("blades", blades).finish(),
+            ApertureShape::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+fn rand_in_unit_polygon<R: Rng + ?Sized>(blades: u32, rng: &mut R) -> Vec2 {
+    if blades < 3 {
+        return rand_in_unit_disc_with(rng);
+    }
 
+    // the n-gon is a fan of equal-area triangles around the center, pick one and sample it uniformly
+    let wedge = std::f32::consts::TAU / blades as f32;
+    let i = rng.gen_range(0..blades) as f32;
+
+    let a = Vec2::from_angle(i * wedge);
+    let b = Vec2::from_angle((i + 1.0) * wedge);
+
+    let (mut s, mut t): (f32, f32) = (rng.gen(), rng.gen());
+
+    if s + t > 1.0 {
+        s = 1.0 - s;
+        t = 1.0 - t;
+    }
+
+    return a * s + b * t;
+}
+
+#[derive(Clone, Debug)]
 pub struct Camera {
     pub origin: Vec3,
     llc: Vec3,
@@ -11,6 +73,8 @@ pub struct Camera {
     cu: Vec3,
     cv: Vec3,
     aperture: f32,
+    aperture_shape: ApertureShape,
+    focal_length: f32,
 }
 
 impl Camera {
@@ -37,11 +101,90 @@ impl Camera {
 
         let llc = origin - (h * 0.5) - (v * 0.5) - focal_length * cw;
 
-        return Camera { origin, llc, horizontal: h, vertical: v, cu, cv, aperture };
+        return Camera {
+            origin,
+            llc,
+            horizontal: h,
+            vertical: v,
+            cu,
+            cv,
+            aperture,
+            aperture_shape: ApertureShape::Circle,
+            focal_length,
+        };
+    }
+
+    // focus distance and aperture diameter, for use as `focal_length` and `aperture` in `new`, that keep everything
+    // between `near` and `far` (which may be infinite) within `max_blur`, the blur circle's angular size in radians
+    pub fn depth_of_field(near: f32, far: f32, max_blur: f32) -> (f32, f32) {
+        // focusing at the harmonic mean of the two distances blurs both by the same amount
+        let focal_length = 2.0 / (1.0 / near + 1.0 / far);
+
+        // a point at distance d spreads over an angle of aperture * |d - focal_length| / (d * focal_length)
+        let spread = (focal_length - near) / (near * focal_length);
+
+        if spread <= f32::EPSILON {
+            return (focal_length, 0.0);
+        }
+
+        return (focal_length, max_blur / spread);
+    }
+
+    pub fn with_aperture_shape(mut self, aperture_shape: ApertureShape) -> Camera {
+        self.aperture_shape = aperture_shape;
+        return self;
+    }
+
+    // rotates the view around the forward axis by `roll` radians
+    pub fn with_roll(mut self, roll: f32) -> Camera {
+        let cw = self.cu.cross(self.cv);
+        let rotation = Quat::from_axis_angle(cw, roll);
+
+        let center = self.llc + self.horizontal * 0.5 + self.vertical * 0.5;
+
+        self.cu = rotation * self.cu;
+        self.cv = rotation * self.cv;
+        self.horizontal = rotation * self.horizontal;
+        self.vertical = rotation * self.vertical;
+        self.llc = center - self.horizontal * 0.5 - self.vertical * 0.5;
+
+        return self;
+    }
+
+    // slides the viewport across the image plane by `shift` times its width and height, like the shift of a view
+    // camera lens; the view direction is unchanged, so vertical lines stay parallel when the camera is level
+    pub fn with_shift(mut self, shift: Vec2) -> Camera {
+        self.llc += shift.x * self.horizontal + shift.y * self.vertical;
+        return self;
+    }
+
+    // moves the plane of focus through `point`, keeping the field of view
+    pub fn focus_on(&mut self, point: Vec3) {
+        let forward = -self.cu.cross(self.cv);
+
+        // the focal plane is perpendicular to the view axis, so use the depth of the point along it rather than the
+        // euclidean distance, which would only be sharp at the image center
+        let focal_length = (point - self.origin).dot(forward);
+
+        if focal_length <= f32::EPSILON {
+            return;
+        }
+
+        let scale = focal_length / self.focal_length;
+
+        self.horizontal *= scale;
+        self.vertical *= scale;
+        self.llc = self.origin + (self.llc - self.origin) * scale;
+        self.focal_length = focal_length;
     }
 
     pub fn create_ray(&self, s: f32, t: f32) -> Ray {
-        let rand_in_lens_disc = rand_in_unit_disc() * self.aperture * 0.5;
+        return self.create_ray_with(s, t, &mut rand::thread_rng());
+    }
+
+    // like `create_ray`, sampling the lens with `rng`
+    pub fn create_ray_with<R: Rng + ?Sized>(&self, s: f32, t: f32, rng: &mut R) -> Ray {
+        let rand_in_lens_disc = self.aperture_shape.sample_with(rng) * self.aperture * 0.5;
         let offset = self.cu * rand_in_lens_disc.x + self.cv * rand_in_lens_disc.y;
 
         return Ray::new(
@@ -49,4 +192,205 @@ impl Camera {
             self.llc + s * self.horizontal + t * self.vertical - self.origin - offset,
         );
     }
+
+    // a ray from the center of the lens, ignoring the aperture, for passes that must not be blurred
+    pub fn create_pinhole_ray(&self, s: f32, t: f32) -> Ray {
+        return Ray::new(self.origin, self.llc + s * self.horizontal + t * self.vertical - self.origin);
+    }
+
+    // appends one ray per (s, t) pair to `out`, sampling the lens with `rng`; gives the same rays as calling
+    // `create_ray_with` for each pair with the same `rng`
+    pub fn create_rays<R: Rng + ?Sized>(&self, samples: &[(f32, f32)], rng: &mut R, out: &mut Vec<Ray>) {
+        out.reserve(samples.len());
+
+        // the lens basis scaled to the lens radius, and the image plane relative to the origin
+        let lens_u = self.cu * (self.aperture * 0.5);
+        let lens_v = self.cv * (self.aperture * 0.5);
+        let to_llc = self.llc - self.origin;
+
+        for &(s, t) in samples {
+            let rand_in_lens_disc = self.aperture_shape.sample_with(rng);
+            let offset = lens_u * rand_in_lens_disc.x + lens_v * rand_in_lens_disc.y;
+
+            out.push(Ray::new(self.origin + offset, to_llc + s * self.horizontal + t * self.vertical - offset));
+        }
+    }
+}
+
+// a pair of parallel cameras for stereoscopic rendering; render each eye separately and combine them, e.g. with
+// `output::anaglyph`
+pub struct StereoCamera {
+    pub left: Camera,
+    pub right: Camera,
+}
+
+impl StereoCamera {
+    // places the eyes `interpupillary_distance` apart along the camera's horizontal axis, centered on `camera`
+    pub fn new(camera: Camera, interpupillary_distance: f32) -> StereoCamera {
+        let offset = camera.cu * (interpupillary_distance * 0.5);
+
+        let mut left = camera.clone();
+        left.origin -= offset;
+        left.llc -= offset;
+
+        let mut right = camera;
+        right.origin += offset;
+        right.llc += offset;
+
+        return StereoCamera { left, right };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn hexagonal_aperture_samples_stay_inside_the_hexagon() {
+        let wedge = std::f32::consts::TAU / 6.0;
+        let apothem = (wedge * 0.5).cos();
+
+        for _ in 0..10_000 {
+            let p = ApertureShape::Polygon { blades: 6 }.sample();
+
+            for i in 0..6 {
+                let edge_normal = Vec2::from_angle((i as f32 + 0.5) * wedge);
+                assert!(p.dot(edge_normal) <= apothem + 1e-5, "{} is outside the hexagon", p);
+            }
+        }
+    }
+
+    #[test]
+    fn circular_aperture_is_uniform_over_the_unit_disc() {
+        const SAMPLES: u32 = 100_000;
+
+        let mut mean = Vec2::ZERO;
+        let mut mean_length_squared = 0.0;
+
+        for _ in 0..SAMPLES {
+            let p = ApertureShape::Circle.sample();
+            assert!(p.length() <= 1.0);
+
+            mean += p / SAMPLES as f32;
+            mean_length_squared += p.length_squared() / SAMPLES as f32;
+        }
+
+        // a uniform disc is centered with E[r^2] = 1/2
+        assert!(mean.length() < 0.01, "mean {}", mean);
+        assert!((mean_length_squared - 0.5).abs() < 0.01, "E[r^2] = {}", mean_length_squared);
+    }
+
+    #[test]
+    fn quarter_roll_swaps_the_image_axes() {
+        let camera = Camera::new(Vec3::new(1.0, 2.0, 3.0), Vec3::ZERO, Vec3::Y, 40.0, 1.0, 0.0, 1.0);
+        let rolled = camera.clone().with_roll(std::f32::consts::FRAC_PI_2);
+
+        assert!(rolled.cu.abs_diff_eq(camera.cv, 1e-5));
+        assert!(rolled.cv.abs_diff_eq(-camera.cu, 1e-5));
+
+        // the right edge of the rolled image is the top edge of the original
+        let right = rolled.create_ray(1.0, 0.5).direction;
+        let top = camera.create_ray(0.5, 1.0).direction;
+        assert!(right.abs_diff_eq(top, 1e-5), "{} vs {}", right, top);
+    }
+
+    #[test]
+    fn focused_center_rays_converge_on_the_target() {
+        let target = Vec3::new(0.0, 0.0, -2.0);
+
+        let mut camera = Camera::new(Vec3::new(0.0, 0.0, 10.0), Vec3::ZERO, Vec3::Y, 40.0, 1.5, 2.0, 3.0);
+        camera.focus_on(target);
+
+        let mut origins = Vec::new();
+        for _ in 0..100 {
+            let r = camera.create_ray(0.5, 0.5);
+            assert!(r.at(1.0).abs_diff_eq(target, 1e-4), "{} misses the target", r.at(1.0));
+            origins.push(r.origin);
+        }
+
+        // the lens really was sampled
+        assert!(origins.iter().any(|&o| !o.abs_diff_eq(camera.origin, 1e-3)));
+    }
+
+    // image coordinates of a world point, where the line from the camera origin crosses the image plane
+    fn project(camera: &Camera, point: Vec3) -> Vec2 {
+        let n = camera.horizontal.cross(camera.vertical);
+        let d = point - camera.origin;
+        let q = camera.origin + d * ((camera.llc - camera.origin).dot(n) / d.dot(n)) - camera.llc;
+
+        return Vec2::new(
+            q.dot(camera.horizontal) / camera.horizontal.length_squared(),
+            q.dot(camera.vertical) / camera.vertical.length_squared(),
+        );
+    }
+
+    #[test]
+    fn vertical_shift_moves_the_image_without_converging_verticals() {
+        let level = Camera::new(Vec3::ZERO, -Vec3::Z, Vec3::Y, 60.0, 1.5, 0.0, 1.0);
+        let shifted = level.clone().with_shift(Vec2::new(0.0, 0.25));
+
+        let point = Vec3::new(0.0, 0.0, -10.0);
+        assert!(project(&level, point).abs_diff_eq(Vec2::splat(0.5), 1e-5));
+        assert!(project(&shifted, point).abs_diff_eq(Vec2::new(0.5, 0.25), 1e-5));
+
+        // both ends of an off-center vertical line stay in the same image column
+        let bottom = project(&shifted, Vec3::new(2.0, -3.0, -10.0));
+        let top = project(&shifted, Vec3::new(2.0, 3.0, -10.0));
+        assert!((bottom.x - top.x).abs() < 1e-5, "{} vs {}", bottom, top);
+    }
+
+    #[test]
+    fn depth_of_field_keeps_the_range_within_the_blur() {
+        let (near, far, max_blur) = (2.0, 10.0, 0.01);
+        let (focal_length, aperture) = Camera::depth_of_field(near, far, max_blur);
+
+        // rays through the lens toward a point at distance d spread over aperture * |d - f| / d on the focal plane
+        let blur = |d: f32| aperture * (d - focal_length).abs() / (d * focal_length);
+
+        assert!(blur(near) <= max_blur * 1.0001);
+        assert!(blur(far) <= max_blur * 1.0001);
+        assert!(blur(1.0) > max_blur);
+        assert!(blur(50.0) > max_blur);
+    }
+
+    #[test]
+    fn batched_rays_match_single_rays() {
+        let samples = [(0.0, 0.0), (0.5, 0.5), (1.0, 0.25), (0.125, 0.875)];
+
+        for shape in [ApertureShape::Circle, ApertureShape::Polygon { blades: 5 }] {
+            let camera = Camera::new(Vec3::new(1.0, 2.0, 3.0), Vec3::ZERO, Vec3::Y, 40.0, 1.5, 0.5, 4.0)
+                .with_aperture_shape(shape);
+
+            let mut rays = Vec::new();
+            camera.create_rays(&samples, &mut StdRng::seed_from_u64(11), &mut rays);
+
+            let mut rng = StdRng::seed_from_u64(11);
+            assert_eq!(rays.len(), samples.len());
+
+            for (r, &(s, t)) in rays.iter().zip(&samples) {
+                let expected = camera.create_ray_with(s, t, &mut rng);
+                assert!(r.origin.abs_diff_eq(expected.origin, 1e-5), "{} vs {}", r.origin, expected.origin);
+                assert!(r.direction.abs_diff_eq(expected.direction, 1e-5));
+            }
+
+            // the lens was actually sampled
+            assert!(rays.iter().any(|r| !r.origin.abs_diff_eq(camera.origin, 1e-3)));
+        }
+    }
+
+    #[test]
+    fn stereo_eyes_are_one_ipd_apart_and_parallel() {
+        let camera = Camera::new(Vec3::new(1.0, 2.0, 3.0), Vec3::ZERO, Vec3::Y, 40.0, 1.5, 0.0, 4.0);
+        let stereo = StereoCamera::new(camera.clone(), 0.065);
+
+        let left = stereo.left.create_ray(0.5, 0.5);
+        let right = stereo.right.create_ray(0.5, 0.5);
+
+        assert!((right.origin - left.origin).abs_diff_eq(camera.cu * 0.065, 1e-6));
+        assert!(left.direction.abs_diff_eq(right.direction, 1e-6));
+        assert!(((left.origin + right.origin) * 0.5).abs_diff_eq(camera.origin, 1e-6));
+    }
 }
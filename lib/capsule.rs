@@ -0,0 +1,196 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::ray::Ray;
+use crate::scatter::Scatter;
+use crate::surface::{Surface, SurfaceIntersection};
+use crate::validation::SurfaceIssue;
+use glam::*;
+
+// a sphere of `radius` swept along the segment from `a` to `b`: a cylinder with hemispherical caps
+pub struct Capsule {
+    a: Vec3,
+    b: Vec3,
+    radius: f32,
+    material: Arc<dyn Scatter>,
+}
+
+impl Capsule {
+    pub fn new(a: Vec3, b: Vec3, radius: f32, material: Arc<dyn Scatter>) -> Capsule {
+        return Capsule { a, b, radius, material };
+    }
+
+    // both roots of a ray against a sphere, in increasing order
+    fn sphere_roots(&self, r: &Ray, center: Vec3) -> Option<[f32; 2]> {
+        let oc = r.origin - center;
+        let a = r.direction.length_squared();
+        let half_b = oc.dot(r.direction);
+        let c = oc.length_squared() - self.radius * self.radius;
+
+        let discriminant = half_b * half_b - a * c;
+
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let discriminant_sqrt = discriminant.sqrt();
+
+        return Some([(-half_b - discriminant_sqrt) / a, (-half_b + discriminant_sqrt) / a]);
+    }
+}
+
+impl Surface for Capsule {
+    fn raycast(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<SurfaceIntersection> {
+        let length = self.a.distance(self.b);
+        let axis = (self.b - self.a).normalize_or_zero();
+
+        // position along the axis, 0 at `a` and `length` at `b`
+        let height = |p: Vec3| (p - self.a).dot(axis);
+
+        let mut t_nearest = t_max;
+        let mut nearest: Option<(f32, Vec3)> = None;
+
+        let mut consider = |t: f32, outward_normal: Vec3| {
+            if t_min <= t && t <= t_nearest {
+                t_nearest = t;
+                nearest = Some((t, outward_normal));
+            }
+        };
+
+        // the body, as an infinite cylinder clipped to the segment; rays parallel to the axis can only hit the caps
+        let oa = r.origin - self.a;
+        let q = oa - axis * oa.dot(axis);
+        let e = r.direction - axis * r.direction.dot(axis);
+
+        let qa = e.length_squared();
+
+        if qa > f32::EPSILON {
+            let half_qb = q.dot(e);
+            let qc = q.length_squared() - self.radius * self.radius;
+            let discriminant = half_qb * half_qb - qa * qc;
+
+            if discriminant >= 0.0 {
+                let discriminant_sqrt = discriminant.sqrt();
+
+                for t in [(-half_qb - discriminant_sqrt) / qa, (-half_qb + discriminant_sqrt) / qa] {
+                    let p = r.at(t);
+                    let y = height(p);
+
+                    if (0.0..=length).contains(&y) {
+                        consider(t, (p - self.a - axis * y) / self.radius);
+                    }
+                }
+            }
+        }
+
+        // each cap only counts on its own side of the body
+        for (center, is_end) in [(self.a, false), (self.b, true)] {
+            if let Some(roots) = self.sphere_roots(r, center) {
+                for t in roots {
+                    let p = r.at(t);
+                    let y = height(p);
+
+                    if (is_end && y >= length) || (!is_end && y <= 0.0) {
+                        consider(t, (p - center) / self.radius);
+                    }
+                }
+            }
+        }
+
+        let (t, outward_normal) = nearest?;
+        let p = r.at(t);
+
+        let facing = r.direction.dot(outward_normal) < 0.0;
+        let normal = if facing { outward_normal } else { -outward_normal };
+
+        return Some(SurfaceIntersection {
+            p,
+            t,
+            facing,
+            normal,
+            material: self.material.clone(),
+            u: 0.0,
+            v: 0.0,
+            dpdu: Vec3::ZERO,
+            dpdv: Vec3::ZERO,
+        });
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = Vec3::splat(self.radius.abs());
+        return Some(Aabb::new(self.a.min(self.b) - r, self.a.max(self.b) + r));
+    }
+
+    fn area(&self) -> Option<f32> {
+        use std::f32::consts::PI;
+        return Some(2.0 * PI * self.radius * self.a.distance(self.b) + 4.0 * PI * self.radius * self.radius);
+    }
+
+    fn validate(&self) -> Option<SurfaceIssue> {
+        if !self.a.is_finite() || !self.b.is_finite() || !self.radius.is_finite() {
+            return Some(SurfaceIssue::NonFinite);
+        }
+
+        if self.radius.abs() < f32::EPSILON || self.a.distance(self.b) < f32::EPSILON {
+            return Some(SurfaceIssue::Degenerate);
+        }
+
+        return None;
+    }
+
+    fn material(&self) -> Option<&Arc<dyn Scatter>> {
+        return Some(&self.material);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::lambertian::LambertianMaterial;
+
+    // a capsule along the y axis from y = -1 to y = 1 with radius 0.5
+    fn capsule() -> Capsule {
+        return Capsule::new(-Vec3::Y, Vec3::Y, 0.5, Arc::new(LambertianMaterial::new(Vec3::ONE)));
+    }
+
+    #[test]
+    fn side_ray_hits_the_cylinder_body() {
+        let hit = capsule().raycast(&Ray::new(Vec3::new(5.0, 0.3, 0.0), -Vec3::X), 0.001, f32::MAX).unwrap();
+
+        assert!(hit.p.abs_diff_eq(Vec3::new(0.5, 0.3, 0.0), 1e-5));
+        assert!(hit.normal.abs_diff_eq(Vec3::X, 1e-5));
+        assert!(hit.facing);
+    }
+
+    #[test]
+    fn hits_top_cap() {
+        let top = Vec3::Y;
+
+        // along the axis, and off the axis beyond the end of the segment where only the rounded cap is
+        for r in [Ray::new(Vec3::Y * 5.0, -Vec3::Y), Ray::new(Vec3::new(5.0, 1.3, 0.2), -Vec3::X)] {
+            let hit = capsule().raycast(&r, 0.001, f32::MAX).unwrap();
+
+            assert!((hit.p.distance(top) - 0.5).abs() < 1e-5, "{} is off the cap", hit.p);
+            assert!(hit.p.y > top.y);
+            assert!(hit.normal.abs_diff_eq((hit.p - top).normalize(), 1e-4), "{}", hit.normal);
+        }
+    }
+
+    #[test]
+    fn hits_bottom_cap() {
+        let bottom = -Vec3::Y;
+
+        for r in [Ray::new(-Vec3::Y * 5.0, Vec3::Y), Ray::new(Vec3::new(-0.2, -1.3, 5.0), -Vec3::Z)] {
+            let hit = capsule().raycast(&r, 0.001, f32::MAX).unwrap();
+
+            assert!((hit.p.distance(bottom) - 0.5).abs() < 1e-5, "{} is off the cap", hit.p);
+            assert!(hit.p.y < bottom.y);
+            assert!(hit.normal.abs_diff_eq((hit.p - bottom).normalize(), 1e-4), "{}", hit.normal);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use glam::*;
+
+use crate::ray::Ray;
+use crate::scatter::Scatter;
+use crate::surface::SurfaceIntersection;
+
+// monte carlo estimate of the total attenuation a material returns for light arriving along `incident` at a flat surface
+// facing +y; an energy conserving material never exceeds one in any channel, apart from what it emits
+pub fn estimate_albedo(material: Arc<dyn Scatter>, incident: Vec3, samples: u32) -> Vec3 {
+    let incident = incident.normalize();
+    let facing = incident.dot(Vec3::Y) < 0.0;
+
+    let intersection = SurfaceIntersection {
+        p: Vec3::ZERO,
+        normal: if facing { Vec3::Y } else { -Vec3::Y },
+        facing,
+        material: material.clone(),
+        t: 1.0,
+        u: 0.5,
+        v: 0.5,
+        dpdu: Vec3::X,
+        dpdv: Vec3::Z,
+    };
+
+    let r = Ray::new(-incident, incident);
+
+    let mut total = DVec3::ZERO;
+
+    for _ in 0..samples {
+        if let Some((attenuation, _)) = material.scatter(&r, &intersection) {
+            total += attenuation.as_dvec3();
+        }
+    }
+
+    return (total / samples.max(1) as f64).as_vec3();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::dielectric::DielectricMaterial;
+    use crate::materials::lambertian::LambertianMaterial;
+    use crate::materials::metal::MetalMaterial;
+    use crate::scatter::FnMaterial;
+
+    fn conserves_energy(albedo: Vec3) -> bool {
+        return albedo.cmple(Vec3::splat(1.0 + 1e-4)).all();
+    }
+
+    #[test]
+    fn builtin_materials_conserve_energy() {
+        let materials: [Arc<dyn Scatter>; 3] = [
+            Arc::new(LambertianMaterial::new(Vec3::new(0.9, 0.5, 0.1))),
+            Arc::new(MetalMaterial::new(Vec3::ONE, 0.3)),
+            Arc::new(DielectricMaterial::new(1.5)),
+        ];
+
+        for material in materials {
+            for incident in [-Vec3::Y, Vec3::new(1.0, -1.0, 0.0), Vec3::new(1.0, -0.05, 0.0), Vec3::Y] {
+                let albedo = estimate_albedo(material.clone(), incident, 2000);
+                assert!(conserves_energy(albedo), "{} returns {} from {}", material.type_name(), albedo, incident);
+            }
+        }
+    }
+
+    // a deliberately broken material that doubles the incoming light, to show the check catches it
+    #[test]
+    fn energy_gaining_material_is_rejected() {
+        let material = Arc::new(FnMaterial::new(Box::new(|_r, intersection| {
+            return Some((Vec3::splat(2.0), Ray::new(intersection.p, intersection.normal)));
+        })));
+
+        let albedo = estimate_albedo(material, -Vec3::Y, 100);
+        assert!(albedo.cmpgt(Vec3::ONE).all());
+        assert!(!conserves_energy(albedo));
+    }
+}
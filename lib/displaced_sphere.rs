@@ -0,0 +1,227 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::ray::Ray;
+use crate::scatter::Scatter;
+use crate::sphere::{sphere_derivatives, sphere_uv};
+use crate::surface::{Surface, SurfaceIntersection};
+use crate::texture::Texture;
+use crate::validation::SurfaceIssue;
+use glam::*;
+
+const DEFAULT_MAX_STEPS: u32 = 256;
+const BISECTION_STEPS: u32 = 24;
+const STEP_SCALE: f32 = 0.8;
+const HIT_EPSILON: f32 = 1e-5;
+
+// a sphere whose radius is offset by the red channel of a height texture, evaluated at the spherical uv
+pub struct DisplacedSphere {
+    center: Vec3,
+    radius: f32,
+    height: Arc<dyn Texture>,
+    // upper bound of the absolute height, used to bound the marched interval
+    max_height: f32,
+    material: Arc<dyn Scatter>,
+    // sphere tracing gives up and reports a miss after this many steps
+    max_steps: u32,
+}
+
+impl DisplacedSphere {
+    pub fn new(
+        center: Vec3,
+        radius: f32,
+        height: Arc<dyn Texture>,
+        max_height: f32,
+        material: Arc<dyn Scatter>,
+    ) -> DisplacedSphere {
+        return DisplacedSphere {
+            center,
+            radius,
+            height,
+            max_height: max_height.abs(),
+            material,
+            max_steps: DEFAULT_MAX_STEPS,
+        };
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u32) -> DisplacedSphere {
+        self.max_steps = max_steps;
+        return self;
+    }
+
+    // signed distance-like function, negative inside the displaced surface
+    fn distance(&self, p: Vec3) -> f32 {
+        let q = p - self.center;
+        let len = q.length();
+        let (u, v) = sphere_uv(q / len);
+        return len - (self.radius + self.height.value(u, v, p).x);
+    }
+
+    fn normal(&self, p: Vec3) -> Vec3 {
+        const H: f32 = 1e-4;
+
+        let dx = self.distance(p + Vec3::X * H) - self.distance(p - Vec3::X * H);
+        let dy = self.distance(p + Vec3::Y * H) - self.distance(p - Vec3::Y * H);
+        let dz = self.distance(p + Vec3::Z * H) - self.distance(p - Vec3::Z * H);
+
+        return Vec3::new(dx, dy, dz).normalize();
+    }
+
+    // the interval of the ray inside the sphere that bounds every possible displacement
+    fn bounds(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<(f32, f32)> {
+        let bound = self.radius + self.max_height;
+
+        let oc = r.origin - self.center;
+        let a = r.direction.length_squared();
+        let half_b = oc.dot(r.direction);
+        let c = oc.length_squared() - (bound * bound);
+
+        let discriminant = (half_b * half_b) - (a * c);
+
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let discriminant_sqrt = discriminant.sqrt();
+
+        let t0 = ((-half_b - discriminant_sqrt) / a).max(t_min);
+        let t1 = ((-half_b + discriminant_sqrt) / a).min(t_max);
+
+        return if t0 < t1 { Some((t0, t1)) } else { None };
+    }
+}
+
+impl Surface for DisplacedSphere {
+    fn raycast(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<SurfaceIntersection> {
+        let (t0, t1) = self.bounds(r, t_min, t_max)?;
+
+        let inv_length = 1.0 / r.direction.length();
+
+        // sphere trace through the bounds until the distance changes sign, then refine the root by bisection
+        let start_sign = self.distance(r.at(t0)).signum();
+
+        let mut t_prev = t0;
+        let mut t = t0;
+        let mut root = None;
+
+        for _ in 0..self.max_steps {
+            let d = self.distance(r.at(t));
+
+            if d.abs() < HIT_EPSILON {
+                root = Some(t);
+                break;
+            }
+
+            if d.signum() != start_sign {
+                let (mut lo, mut hi) = (t_prev, t);
+
+                for _ in 0..BISECTION_STEPS {
+                    let mid = 0.5 * (lo + hi);
+                    if self.distance(r.at(mid)).signum() == start_sign {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+
+                root = Some(0.5 * (lo + hi));
+                break;
+            }
+
+            t_prev = t;
+            t += (d.abs() * STEP_SCALE).max(HIT_EPSILON) * inv_length;
+
+            if t > t1 {
+                break;
+            }
+        }
+
+        let t = root?;
+        let p = r.at(t);
+
+        let outward_normal = self.normal(p);
+        let facing = r.direction.dot(outward_normal) < 0.0;
+        let normal = if facing { outward_normal } else { -outward_normal };
+
+        let q = p - self.center;
+        let (u, v) = sphere_uv(q.normalize());
+        let (dpdu, dpdv) = sphere_derivatives(q, q.length());
+
+        return Some(SurfaceIntersection { p, t, facing, normal, material: self.material.clone(), u, v, dpdu, dpdv });
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = Vec3::splat(self.radius + self.max_height);
+        return Some(Aabb::new(self.center - r, self.center + r));
+    }
+
+    // approximated by the undisplaced sphere
+    fn area(&self) -> Option<f32> {
+        return Some(4.0 * std::f32::consts::PI * self.radius * self.radius);
+    }
+
+    fn validate(&self) -> Option<SurfaceIssue> {
+        if !self.center.is_finite() || !self.radius.is_finite() || !self.max_height.is_finite() {
+            return Some(SurfaceIssue::NonFinite);
+        }
+
+        if self.radius + self.max_height < f32::EPSILON {
+            return Some(SurfaceIssue::Degenerate);
+        }
+
+        return None;
+    }
+
+    fn material(&self) -> Option<&Arc<dyn Scatter>> {
+        return Some(&self.material);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::lambertian::LambertianMaterial;
+    use crate::sphere::Sphere;
+    use crate::texture::SolidColor;
+    use crate::util::rand_on_unit_sphere;
+
+    #[test]
+    fn constant_displacement_matches_a_larger_sphere() {
+        let material = Arc::new(LambertianMaterial::new(Vec3::ONE));
+        let center = Vec3::new(0.5, 1.0, -2.0);
+
+        let height = Arc::new(SolidColor::new(Vec3::splat(0.1)));
+        let displaced = DisplacedSphere::new(center, 1.0, height, 0.2, material.clone());
+        let sphere = Sphere::new(center, 1.1, material);
+
+        for _ in 0..200 {
+            let origin = center + rand_on_unit_sphere() * 4.0;
+            let target = center + rand_on_unit_sphere() * 0.5;
+            let r = Ray::new(origin, target - origin);
+
+            let expected = sphere.raycast(&r, 0.001, f32::MAX).unwrap();
+            let actual = displaced.raycast(&r, 0.001, f32::MAX).unwrap();
+
+            assert!((expected.t - actual.t).abs() < 1e-3, "t {} vs {}", actual.t, expected.t);
+            assert!(expected.normal.dot(actual.normal) > 0.999);
+        }
+    }
+
+    #[test]
+    fn step_limit_cuts_the_march_short() {
+        let material = Arc::new(LambertianMaterial::new(Vec3::ONE));
+        let height = Arc::new(SolidColor::new(Vec3::ZERO));
+
+        // starting at the edge of the bounds, one step can't reach the surface
+        let r = Ray::new(Vec3::new(0.0, 0.0, 5.0), -Vec3::Z);
+
+        let displaced = DisplacedSphere::new(Vec3::ZERO, 1.0, height, 0.5, material);
+        assert!(displaced.raycast(&r, 0.001, f32::MAX).is_some());
+        assert!(displaced.with_max_steps(1).raycast(&r, 0.001, f32::MAX).is_none());
+    }
+}
@@ -0,0 +1,118 @@
+use glam::*;
+
+use crate::ray::Ray;
+
+// radiance arriving from rays that escape the scene
+pub trait Environment: Send + Sync {
+    fn background(&self, r: &Ray) -> Vec3;
+}
+
+// blends between two colors by the ray direction's projection onto `axis`
+pub struct GradientSky {
+    axis: Vec3,
+    color_from: Vec3,
+    color_to: Vec3,
+}
+
+impl GradientSky {
+    pub fn new(axis: Vec3, color_from: Vec3, color_to: Vec3) -> GradientSky {
+        GradientSky { axis: axis.normalize(), color_from, color_to }
+    }
+}
+
+impl Default for GradientSky {
+    fn default() -> GradientSky {
+        GradientSky::new(Vec3::Y, Vec3::new(1.0, 1.0, 1.0), Vec3::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl Environment for GradientSky {
+    fn background(&self, r: &Ray) -> Vec3 {
+        let ray_dir_normalized = r.direction.normalize();
+
+        let t = 0.5 * (ray_dir_normalized.dot(self.axis) + 1.0);
+
+        return Vec3::lerp(self.color_from, self.color_to, t);
+    }
+}
+
+// a rough single-scattering sky: rayleigh scattering gives a blue zenith, the long optical path near the horizon
+// reddens sunlight, turbidity adds whitish haze, and rays close to the sun direction see the sun disc
+pub struct PhysicalSky {
+    sun_direction: Vec3,
+    turbidity: f32,
+}
+
+impl PhysicalSky {
+    const RAYLEIGH: Vec3 = Vec3::new(0.058, 0.135, 0.331);
+    const MIE: f32 = 0.021;
+    const MIE_G: f32 = 0.76;
+    const SUN_ANGULAR_RADIUS: f32 = 0.01;
+    const SUN_INTENSITY: f32 = 20.0;
+
+    pub fn new(sun_direction: Vec3, turbidity: f32) -> PhysicalSky {
+        PhysicalSky { sun_direction: sun_direction.normalize(), turbidity: turbidity.max(1.0) }
+    }
+
+    // relative length of the path through the atmosphere for a direction, growing towards the horizon
+    fn air_mass(direction: Vec3) -> f32 {
+        return 1.0 / (direction.y.max(0.0) + 0.05);
+    }
+}
+
+impl Environment for PhysicalSky {
+    fn background(&self, r: &Ray) -> Vec3 {
+        let direction = r.direction.normalize();
+        let cos_sun = direction.dot(self.sun_direction);
+
+        let beta_r = PhysicalSky::RAYLEIGH;
+        let beta_m = Vec3::splat(PhysicalSky::MIE * (self.turbidity - 1.0) * 0.5);
+        let beta = beta_r + beta_m;
+
+        // sunlight reaching the scattering air, attenuated along the sun's own path
+        let sun_transmittance = (-beta * PhysicalSky::air_mass(self.sun_direction)).exp();
+
+        let rayleigh_phase = 0.75 * (1.0 + cos_sun * cos_sun);
+        let g = PhysicalSky::MIE_G;
+        let mie_phase = (1.0 - g * g) / (4.0 * std::f32::consts::PI * (1.0 + g * g - 2.0 * g * cos_sun).powf(1.5));
+
+        let view_depth = beta * PhysicalSky::air_mass(direction);
+        let inscatter = (beta_r * rayleigh_phase + beta_m * mie_phase) / beta * (Vec3::ONE - (-view_depth).exp());
+
+        let mut color = sun_transmittance * inscatter;
+
+        if cos_sun > PhysicalSky::SUN_ANGULAR_RADIUS.cos() && direction.y >= 0.0 {
+            color += sun_transmittance * PhysicalSky::SUN_INTENSITY;
+        }
+
+        return color;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::luminance;
+
+    #[test]
+    fn x_axis_gradient_differs_between_left_and_right() {
+        let sky = GradientSky::new(Vec3::X, Vec3::ZERO, Vec3::ONE);
+
+        let left = sky.background(&Ray::new(Vec3::ZERO, -Vec3::X));
+        let right = sky.background(&Ray::new(Vec3::ZERO, Vec3::X));
+
+        assert_eq!(left, Vec3::ZERO);
+        assert_eq!(right, Vec3::ONE);
+    }
+
+    #[test]
+    fn sun_is_brighter_than_the_zenith() {
+        let sun_direction = Vec3::new(1.0, 0.5, 0.0).normalize();
+        let sky = PhysicalSky::new(sun_direction, 2.0);
+
+        let sun = luminance(sky.background(&Ray::new(Vec3::ZERO, sun_direction)));
+        let zenith = luminance(sky.background(&Ray::new(Vec3::ZERO, Vec3::Y)));
+
+        assert!(sun > zenith, "sun {} zenith {}", sun, zenith);
+    }
+}
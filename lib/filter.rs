@@ -0,0 +1,72 @@
+use glam::*;
+
+// reconstruction filter used to weight sub-pixel samples
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PixelFilter {
+    Box,
+    // a triangle over the same footprint as the box, weighting samples near the pixel center more
+    Tent,
+    Gaussian,
+}
+
+impl PixelFilter {
+    // half-width of the filter footprint, in pixels
+    pub fn radius(&self) -> f32 {
+        match self {
+            PixelFilter::Box => 0.5,
+            PixelFilter::Tent => 0.5,
+            PixelFilter::Gaussian => 1.5,
+        }
+    }
+
+    // weight of a sample at `offset` pixels from the pixel center
+    pub fn weight(&self, offset: Vec2) -> f32 {
+        match self {
+            PixelFilter::Box => 1.0,
+            PixelFilter::Tent => {
+                let r = self.radius();
+                (1.0 - offset.x.abs() / r).max(0.0) * (1.0 - offset.y.abs() / r).max(0.0)
+            }
+            PixelFilter::Gaussian => {
+                const SIGMA: f32 = 0.5;
+                (-offset.length_squared() / (2.0 * SIGMA * SIGMA)).exp()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // filtered value of a pixel whose center is `distance` pixels to the dark side of a vertical black/white edge,
+    // integrated over a regular grid of sample offsets
+    fn filtered_edge(filter: PixelFilter, distance: f32) -> f32 {
+        const STEPS: u32 = 200;
+
+        let r = filter.radius();
+        let (mut sum, mut weight_sum) = (0.0, 0.0);
+
+        for i in 0..STEPS {
+            for j in 0..STEPS {
+                let offset = Vec2::new(i as f32 + 0.5, j as f32 + 0.5) / STEPS as f32 * 2.0 * r - Vec2::splat(r);
+                let weight = filter.weight(offset);
+                let value = if offset.x > distance { 1.0 } else { 0.0 };
+
+                sum += weight * value;
+                weight_sum += weight;
+            }
+        }
+
+        return sum / weight_sum;
+    }
+
+    #[test]
+    fn tent_has_less_halo_than_box_at_an_edge() {
+        let box_halo = filtered_edge(PixelFilter::Box, 0.3);
+        let tent_halo = filtered_edge(PixelFilter::Tent, 0.3);
+
+        assert!((box_halo - 0.2).abs() < 1e-2, "box picked up {}", box_halo);
+        assert!(tent_halo < box_halo, "tent {} vs box {}", tent_halo, box_halo);
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::layer::Layered;
+use crate::materials::dielectric::DielectricMaterial;
+use crate::materials::diffuse_light::DiffuseLightMaterial;
+use crate::materials::lambertian::LambertianMaterial;
+use crate::materials::metal::MetalMaterial;
+use crate::scatter::Scatter;
+use crate::sphere::Sphere;
+use crate::surface::Surface;
+use crate::world::World;
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaterialKind {
+    Lambertian = 0,
+    Metal = 1,
+    Dielectric = 2,
+    DiffuseLight = 3,
+}
+
+// plain old data laid out for upload to a gpu buffer
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SphereData {
+    pub center: [f32; 3],
+    pub radius: f32,
+    // index into `FlatScene::materials`
+    pub material: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MaterialData {
+    pub kind: MaterialKind,
+    // albedo, or emitted radiance for lights
+    pub color: [f32; 3],
+    // fuzz for metals, index of refraction for dielectrics, unused otherwise
+    pub param: f32,
+}
+
+pub struct FlatScene {
+    pub spheres: Vec<SphereData>,
+    pub materials: Vec<MaterialData>,
+    // indices of surfaces that aren't spheres or use a material with no flat equivalent
+    pub unsupported: Vec<usize>,
+}
+
+pub(crate) fn flatten_world(world: &World) -> FlatScene {
+    let mut scene = FlatScene { spheres: Vec::new(), materials: Vec::new(), unsupported: Vec::new() };
+    let mut material_indices: HashMap<*const (), u32> = HashMap::new();
+
+    for (index, obj) in world.surfaces.iter().enumerate() {
+        // layers don't exist in the flat scene, so look through them to the wrapped surface
+        let mut surface: &dyn Surface = obj.as_ref();
+        while let Some(layered) = surface.as_any().downcast_ref::<Layered>() {
+            surface = layered.inner();
+        }
+
+        let sphere = match surface.as_any().downcast_ref::<Sphere>() {
+            Some(sphere) => sphere,
+            None => {
+                scene.unsupported.push(index);
+                continue;
+            }
+        };
+
+        let key = Arc::as_ptr(sphere.material()) as *const ();
+
+        let material = match material_indices.get(&key) {
+            Some(&material) => material,
+            None => match flatten_material(sphere.material().as_ref()) {
+                Some(data) => {
+                    let material = scene.materials.len() as u32;
+                    scene.materials.push(data);
+                    material_indices.insert(key, material);
+                    material
+                }
+                None => {
+                    scene.unsupported.push(index);
+                    continue;
+                }
+            },
+        };
+
+        scene.spheres.push(SphereData { center: sphere.center().to_array(), radius: sphere.radius(), material });
+    }
+
+    return scene;
+}
+
+// textures and sampling options have no flat equivalent and are dropped
+fn flatten_material(material: &dyn Scatter) -> Option<MaterialData> {
+    let any = material.as_any();
+
+    if let Some(m) = any.downcast_ref::<LambertianMaterial>() {
+        return Some(MaterialData { kind: MaterialKind::Lambertian, color: m.albedo().to_array(), param: 0.0 });
+    }
+
+    if let Some(m) = any.downcast_ref::<MetalMaterial>() {
+        return Some(MaterialData { kind: MaterialKind::Metal, color: m.albedo().to_array(), param: m.fuzz() });
+    }
+
+    if let Some(m) = any.downcast_ref::<DielectricMaterial>() {
+        return Some(MaterialData { kind: MaterialKind::Dielectric, color: [1.0; 3], param: m.index_of_refraction() });
+    }
+
+    if let Some(m) = any.downcast_ref::<DiffuseLightMaterial>() {
+        return Some(MaterialData { kind: MaterialKind::DiffuseLight, color: m.emit().to_array(), param: 0.0 });
+    }
+
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::*;
+
+    use super::*;
+    use crate::quad::Quad;
+
+    #[test]
+    fn spheres_share_one_entry_per_distinct_material() {
+        let red: Arc<dyn Scatter> = Arc::new(LambertianMaterial::new(Vec3::X));
+        let glass: Arc<dyn Scatter> = Arc::new(DielectricMaterial::new(1.5));
+
+        let mut world = World::new();
+        world.surfaces.push(Box::new(Sphere::new(Vec3::ZERO, 1.0, red.clone())));
+        world.surfaces.push(Box::new(Layered::new(Box::new(Sphere::new(Vec3::X, 2.0, glass.clone())), 0b10)));
+        world.surfaces.push(Box::new(Quad::new(Vec3::ZERO, Vec3::X, Vec3::Y, red.clone())));
+        world.surfaces.push(Box::new(Sphere::new(Vec3::Y, 3.0, red)));
+        world.surfaces.push(Box::new(Sphere::new(Vec3::Z, 4.0, glass)));
+
+        let scene = world.flatten();
+
+        assert_eq!(scene.unsupported, vec![2]);
+        assert_eq!(scene.materials.len(), 2);
+        assert_eq!(scene.materials[0].kind, MaterialKind::Lambertian);
+        assert_eq!(scene.materials[1].kind, MaterialKind::Dielectric);
+
+        let spheres: Vec<(f32, u32)> = scene.spheres.iter().map(|s| (s.radius, s.material)).collect();
+        assert_eq!(spheres, vec![(1.0, 0), (2.0, 1), (3.0, 0), (4.0, 1)]);
+    }
+}
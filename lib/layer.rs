@@ -0,0 +1,83 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::mesh::TriangleMesh;
+use crate::ray::Ray;
+use crate::scatter::Scatter;
+use crate::surface::{Surface, SurfaceIntersection};
+use crate::validation::SurfaceIssue;
+use glam::*;
+
+// assigns a surface to a set of render layers, and optionally an id for the object id pass
+pub struct Layered {
+    surface: Box<dyn Surface>,
+    layer: u32,
+    object_id: Option<u32>,
+}
+
+impl Layered {
+    pub fn new(surface: Box<dyn Surface>, layer: u32) -> Layered {
+        return Layered { surface, layer, object_id: None };
+    }
+
+    // 0 is the background in the object id pass, so ids should be nonzero
+    pub fn with_object_id(mut self, object_id: u32) -> Layered {
+        self.object_id = Some(object_id);
+        return self;
+    }
+
+    pub fn inner(&self) -> &dyn Surface {
+        return self.surface.as_ref();
+    }
+}
+
+impl Surface for Layered {
+    fn raycast(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<SurfaceIntersection> {
+        return self.surface.raycast(r, t_min, t_max);
+    }
+
+    fn occluded(&self, r: &Ray, t_min: f32, t_max: f32) -> bool {
+        return self.surface.occluded(r, t_min, t_max);
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        return self.surface.bounding_box();
+    }
+
+    fn bounding_sphere(&self) -> Option<(Vec3, f32)> {
+        return self.surface.bounding_sphere();
+    }
+
+    fn area(&self) -> Option<f32> {
+        return self.surface.area();
+    }
+
+    fn validate(&self) -> Option<SurfaceIssue> {
+        return self.surface.validate();
+    }
+
+    fn tessellate(&self, detail: u32) -> Option<TriangleMesh> {
+        return self.surface.tessellate(detail);
+    }
+
+    fn layer(&self) -> u32 {
+        return self.layer;
+    }
+
+    fn object_id(&self) -> Option<u32> {
+        return self.object_id.or_else(|| self.surface.object_id());
+    }
+
+    fn type_name(&self) -> &'static str {
+        return self.surface.type_name();
+    }
+
+    fn material(&self) -> Option<&Arc<dyn Scatter>> {
+        return self.surface.material();
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
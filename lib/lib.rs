@@ -1,8 +1,30 @@
+#![allow(clippy::needless_return)]
+
+pub mod aabb;
 pub mod camera;
+pub mod capsule;
+pub mod diagnostics;
+pub mod displaced_sphere;
+pub mod environment;
+pub mod filter;
+pub mod flat;
+pub mod layer;
 pub mod materials;
+pub mod medium;
+pub mod mesh;
+pub mod output;
+pub mod progress;
+pub mod quad;
 pub mod ray;
+pub mod renderer;
 pub mod scatter;
+pub mod scene;
+pub mod sdf;
 pub mod sphere;
 pub mod surface;
+#[cfg(test)]
+mod test_util;
+pub mod texture;
 pub mod util;
+pub mod validation;
 pub mod world;
@@ -1,3 +1,9 @@
+pub mod blend;
+pub mod conductor;
 pub mod dielectric;
+pub mod diffuse_light;
 pub mod lambertian;
 pub mod metal;
+pub mod painted_metal;
+pub mod presets;
+pub mod thin_film;
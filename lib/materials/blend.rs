@@ -0,0 +1,111 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::ray::Ray;
+use crate::scatter::Scatter;
+use crate::surface::SurfaceIntersection;
+use crate::texture::Texture;
+use crate::util::luminance;
+use glam::*;
+use rand::*;
+
+// stochastically picks one of two materials per ray, choosing `b` with probability `weight`
+pub struct BlendMaterial {
+    a: Arc<dyn Scatter>,
+    b: Arc<dyn Scatter>,
+    weight: f32,
+    // when set, replaces `weight` with the luminance of the mask at the hit
+    mask: Option<Arc<dyn Texture>>,
+}
+
+impl BlendMaterial {
+    pub fn new(a: Arc<dyn Scatter>, b: Arc<dyn Scatter>, weight: f32) -> BlendMaterial {
+        BlendMaterial { a, b, weight: weight.clamp(0.0, 1.0), mask: None }
+    }
+
+    // varies the weight across the surface, e.g. a lambertian `a` and a smooth metal `b` give wet patches wherever
+    // the mask is bright
+    pub fn with_mask(mut self, mask: Arc<dyn Texture>) -> BlendMaterial {
+        self.mask = Some(mask);
+        return self;
+    }
+
+    fn weight_at(&self, intersection: &SurfaceIntersection) -> f32 {
+        return match &self.mask {
+            Some(mask) => luminance(mask.value(intersection.u, intersection.v, intersection.p)).clamp(0.0, 1.0),
+            None => self.weight,
+        };
+    }
+}
+
+impl Scatter for BlendMaterial {
+    fn scatter(&self, r: &Ray, intersection: &SurfaceIntersection) -> Option<(Vec3, Ray)> {
+        return if self.weight_at(intersection) > thread_rng().gen() {
+            self.b.scatter(r, intersection)
+        } else {
+            self.a.scatter(r, intersection)
+        };
+    }
+
+    fn is_specular(&self) -> bool {
+        return self.a.is_specular() && self.b.is_specular();
+    }
+
+    fn emitted(&self, intersection: &SurfaceIntersection) -> Vec3 {
+        return Vec3::lerp(self.a.emitted(intersection), self.b.emitted(intersection), self.weight_at(intersection));
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::lambertian::LambertianMaterial;
+    use crate::materials::metal::MetalMaterial;
+    use crate::scatter::FnMaterial;
+    use crate::test_util::hit;
+    use crate::texture::SolidColor;
+
+    // a material that always scatters straight up with a recognizable attenuation
+    fn tagged(tag: Vec3) -> Arc<dyn Scatter> {
+        return Arc::new(FnMaterial::new(Box::new(move |_, intersection| {
+            Some((tag, Ray::new(intersection.p, intersection.normal)))
+        })));
+    }
+
+    #[test]
+    fn children_are_picked_in_proportion_to_the_weight() {
+        const SAMPLES: u32 = 100_000;
+
+        let blend = Arc::new(BlendMaterial::new(tagged(Vec3::X), tagged(Vec3::Y), 0.3));
+        let intersection = hit(Vec3::ZERO, Vec3::Y, blend.clone());
+        let r = Ray::new(Vec3::Y, -Vec3::Y);
+
+        let picked_b = (0..SAMPLES).filter(|_| blend.scatter(&r, &intersection).unwrap().0 == Vec3::Y).count();
+        let fraction = picked_b as f32 / SAMPLES as f32;
+
+        assert!((fraction - 0.3).abs() < 0.01, "b picked {} of the time", fraction);
+    }
+
+    #[test]
+    fn mask_selects_between_the_children() {
+        let diffuse: Arc<dyn Scatter> = Arc::new(LambertianMaterial::new(Vec3::X));
+        let glossy: Arc<dyn Scatter> = Arc::new(MetalMaterial::new(Vec3::Y, 0.0));
+        let r = Ray::new(Vec3::new(-1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0));
+
+        for (mask, expected) in [(0.0, Vec3::X), (1.0, Vec3::Y)] {
+            let blend = Arc::new(
+                BlendMaterial::new(diffuse.clone(), glossy.clone(), 0.0)
+                    .with_mask(Arc::new(SolidColor::new(Vec3::splat(mask)))),
+            );
+            let intersection = hit(Vec3::ZERO, Vec3::Y, blend.clone());
+
+            for _ in 0..1000 {
+                assert_eq!(blend.scatter(&r, &intersection).unwrap().0, expected);
+            }
+        }
+    }
+}
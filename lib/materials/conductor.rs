@@ -0,0 +1,98 @@
+use std::any::Any;
+
+use crate::ray::Ray;
+use crate::scatter::Scatter;
+use crate::surface::SurfaceIntersection;
+use crate::util::{rand_in_unit_sphere, reflect};
+use glam::*;
+
+// a metal described by its complex index of refraction n + ik per rgb channel, tinted by the exact conductor fresnel
+pub struct ConductorMaterial {
+    eta: Vec3,
+    k: Vec3,
+    fuzz: f32,
+}
+
+impl ConductorMaterial {
+    pub fn new(eta: Vec3, k: Vec3, fuzz: f32) -> ConductorMaterial {
+        ConductorMaterial { eta, k, fuzz }
+    }
+
+    pub fn gold(fuzz: f32) -> ConductorMaterial {
+        ConductorMaterial::new(Vec3::new(0.143, 0.374, 1.442), Vec3::new(3.983, 2.385, 1.603), fuzz)
+    }
+
+    pub fn copper(fuzz: f32) -> ConductorMaterial {
+        ConductorMaterial::new(Vec3::new(0.200, 0.924, 1.102), Vec3::new(3.912, 2.452, 2.142), fuzz)
+    }
+
+    pub fn aluminum(fuzz: f32) -> ConductorMaterial {
+        ConductorMaterial::new(Vec3::new(1.657, 0.880, 0.521), Vec3::new(9.224, 6.270, 4.837), fuzz)
+    }
+
+    // unpolarized fresnel reflectance per channel for light arriving at `cos_theta` to the normal
+    pub fn reflectance(&self, cos_theta: f32) -> Vec3 {
+        let cos2 = cos_theta * cos_theta;
+        let sin2 = 1.0 - cos2;
+
+        let eta2 = self.eta * self.eta;
+        let k2 = self.k * self.k;
+
+        let t0 = eta2 - k2 - Vec3::splat(sin2);
+        let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).powf(0.5);
+        let t1 = a2_plus_b2 + Vec3::splat(cos2);
+        let a = (0.5 * (a2_plus_b2 + t0)).max(Vec3::ZERO).powf(0.5);
+        let t2 = 2.0 * cos_theta * a;
+        let rs = (t1 - t2) / (t1 + t2);
+
+        let t3 = cos2 * a2_plus_b2 + Vec3::splat(sin2 * sin2);
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+
+        return 0.5 * (rp + rs);
+    }
+}
+
+impl Scatter for ConductorMaterial {
+    fn scatter(&self, r: &Ray, intersection: &SurfaceIntersection) -> Option<(Vec3, Ray)> {
+        let r_direction_norm = r.direction.normalize();
+        let cos_theta = intersection.normal.dot(-r_direction_norm).clamp(0.0, 1.0);
+
+        let reflected_direction = reflect(r_direction_norm, intersection.normal);
+        let scattered_direction = reflected_direction + rand_in_unit_sphere() * self.fuzz;
+        let scattered = Ray::new(intersection.p, scattered_direction);
+
+        return if scattered.direction.dot(intersection.normal) > 0.0 {
+            Some((self.reflectance(cos_theta), scattered))
+        } else {
+            None
+        };
+    }
+
+    fn is_specular(&self) -> bool {
+        return self.fuzz == 0.0;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gold_reflects_more_red_than_blue() {
+        let gold = ConductorMaterial::gold(0.0);
+        let normal = gold.reflectance(1.0);
+
+        // ((n - 1)^2 + k^2) / ((n + 1)^2 + k^2) per channel
+        let expected = ((gold.eta - 1.0).powf(2.0) + gold.k * gold.k) / ((gold.eta + 1.0).powf(2.0) + gold.k * gold.k);
+        assert!(normal.abs_diff_eq(expected, 1e-4), "{} vs {}", normal, expected);
+        assert!(normal.x > normal.z);
+
+        // every metal approaches a perfect mirror at grazing incidence
+        assert!(gold.reflectance(0.0).abs_diff_eq(Vec3::ONE, 1e-4));
+    }
+}
@@ -1,3 +1,5 @@
+use std::any::Any;
+
 use crate::ray::Ray;
 use crate::scatter::Scatter;
 use crate::surface::SurfaceIntersection;
@@ -5,13 +7,32 @@ use crate::util::{reflect, refract};
 use glam::*;
 use rand::*;
 
+// overrides the fresnel choice between reflection and refraction, for debugging glass
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DielectricDebug {
+    Both,
+    ReflectOnly,
+    // still reflects under total internal reflection, where refraction is impossible
+    RefractOnly,
+}
+
 pub struct DielectricMaterial {
     index_of_refraction: f32,
+    debug: DielectricDebug,
 }
 
 impl DielectricMaterial {
     pub fn new(index_of_refraction: f32) -> DielectricMaterial {
-        DielectricMaterial { index_of_refraction }
+        DielectricMaterial { index_of_refraction, debug: DielectricDebug::Both }
+    }
+
+    pub fn with_debug(mut self, debug: DielectricDebug) -> DielectricMaterial {
+        self.debug = debug;
+        return self;
+    }
+
+    pub fn index_of_refraction(&self) -> f32 {
+        return self.index_of_refraction;
     }
 }
 
@@ -28,7 +49,13 @@ impl Scatter for DielectricMaterial {
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
         let schlick_approx = reflectance(cos_theta, refraction_ratio);
 
-        let scattered_direction = if cannot_refract || schlick_approx > thread_rng().gen() {
+        let reflects = match self.debug {
+            DielectricDebug::Both => cannot_refract || schlick_approx > thread_rng().gen(),
+            DielectricDebug::ReflectOnly => true,
+            DielectricDebug::RefractOnly => cannot_refract,
+        };
+
+        let scattered_direction = if reflects {
             reflect(r_direction_norm, intersection.normal) // cannot refract
         } else {
             refract(r_direction_norm, intersection.normal, refraction_ratio)
@@ -38,9 +65,52 @@ impl Scatter for DielectricMaterial {
 
         Some((Vec3::ONE, scattered))
     }
+
+    fn is_specular(&self) -> bool {
+        return true;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
 }
 
 fn reflectance(cos_theta: f32, refraction_ratio: f32) -> f32 {
     let r = ((1.0 - refraction_ratio) / (1.0 + refraction_ratio)).powi(2);
     return r + (1.0 - r) * (1.0 - cos_theta).powi(5);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::test_util::hit;
+
+    // whether a scatter off a horizontal interface went back up rather than through it
+    fn reflects(debug: DielectricDebug, incident: Vec3, facing: bool) -> bool {
+        let material = Arc::new(DielectricMaterial::new(1.5).with_debug(debug));
+        let mut intersection = hit(Vec3::ZERO, Vec3::Y, material.clone());
+        intersection.facing = facing;
+
+        let (_, scattered) = material.scatter(&Ray::new(-incident, incident), &intersection).unwrap();
+        return scattered.direction.dot(Vec3::Y) > 0.0;
+    }
+
+    #[test]
+    fn debug_modes_isolate_reflection_and_refraction() {
+        let steep = Vec3::new(0.2, -1.0, 0.0);
+        // beyond the critical angle when leaving the glass
+        let grazing = Vec3::new(1.0, -0.1, 0.0);
+
+        for _ in 0..100 {
+            assert!(reflects(DielectricDebug::ReflectOnly, steep, true));
+            assert!(reflects(DielectricDebug::ReflectOnly, steep, false));
+
+            assert!(!reflects(DielectricDebug::RefractOnly, steep, true));
+            assert!(!reflects(DielectricDebug::RefractOnly, grazing, true));
+            assert!(!reflects(DielectricDebug::RefractOnly, steep, false));
+            assert!(reflects(DielectricDebug::RefractOnly, grazing, false));
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::any::Any;
+
+use crate::ray::Ray;
+use crate::scatter::Scatter;
+use crate::surface::SurfaceIntersection;
+use glam::*;
+
+pub struct DiffuseLightMaterial {
+    emit: Vec3,
+    // single-sided emitters are dark when hit from behind
+    two_sided: bool,
+}
+
+impl DiffuseLightMaterial {
+    pub fn new(emit: Vec3) -> DiffuseLightMaterial {
+        DiffuseLightMaterial { emit, two_sided: false }
+    }
+
+    pub fn emit(&self) -> Vec3 {
+        return self.emit;
+    }
+
+    pub fn with_two_sided(mut self, two_sided: bool) -> DiffuseLightMaterial {
+        self.two_sided = two_sided;
+        return self;
+    }
+}
+
+impl Scatter for DiffuseLightMaterial {
+    fn scatter(&self, _r: &Ray, _intersection: &SurfaceIntersection) -> Option<(Vec3, Ray)> {
+        return None;
+    }
+
+    fn emitted(&self, intersection: &SurfaceIntersection) -> Vec3 {
+        return if intersection.facing || self.two_sided { self.emit } else { Vec3::ZERO };
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::quad::Quad;
+    use crate::surface::Surface;
+
+    fn emitted_from(light: DiffuseLightMaterial, origin: Vec3) -> Vec3 {
+        // a unit quad in the xy plane, facing +z
+        let quad = Quad::new(Vec3::new(-0.5, -0.5, 0.0), Vec3::X, Vec3::Y, Arc::new(light));
+        let hit = quad.raycast(&Ray::new(origin, -origin), 0.001, f32::MAX).unwrap();
+        return hit.material.emitted(&hit);
+    }
+
+    #[test]
+    fn single_sided_emitter_is_dark_from_behind() {
+        let emit = Vec3::new(4.0, 3.0, 2.0);
+
+        assert_eq!(emitted_from(DiffuseLightMaterial::new(emit), Vec3::Z), emit);
+        assert_eq!(emitted_from(DiffuseLightMaterial::new(emit), -Vec3::Z), Vec3::ZERO);
+        assert_eq!(emitted_from(DiffuseLightMaterial::new(emit).with_two_sided(true), -Vec3::Z), emit);
+    }
+}
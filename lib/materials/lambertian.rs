@@ -1,29 +1,100 @@
+use std::any::Any;
+
 use crate::ray::Ray;
 use crate::scatter::Scatter;
 use crate::surface::SurfaceIntersection;
-use crate::util::{is_near_zero, rand_on_unit_sphere};
+use crate::util::{build_onb, is_near_zero, rand_in_unit_disc, rand_on_unit_sphere};
 use glam::*;
 
+// how diffuse scatter directions are sampled around the surface normal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffuseMethod {
+    // cosine weighted hemisphere sampling (malley's method)
+    Cosine,
+    // uniform over the hemisphere of the normal
+    UniformHemisphere,
+    // the normal offset by a random point on the unit sphere
+    AroundNormal,
+}
+
 pub struct LambertianMaterial {
     albedo: Vec3,
+    method: DiffuseMethod,
 }
 
 impl LambertianMaterial {
     pub fn new(albedo: Vec3) -> LambertianMaterial {
-        LambertianMaterial { albedo }
+        LambertianMaterial { albedo, method: DiffuseMethod::AroundNormal }
+    }
+
+    pub fn with_method(mut self, method: DiffuseMethod) -> LambertianMaterial {
+        self.method = method;
+        return self;
+    }
+
+    pub fn albedo(&self) -> Vec3 {
+        return self.albedo;
     }
 }
 
 impl Scatter for LambertianMaterial {
     fn scatter(&self, _r: &Ray, intersection: &SurfaceIntersection) -> Option<(Vec3, Ray)> {
-        let mut scattered_direction = intersection.normal + rand_on_unit_sphere();
+        let normal = intersection.normal;
+
+        let mut scattered_direction = match self.method {
+            DiffuseMethod::Cosine => {
+                let (tangent, bitangent) = build_onb(normal);
+                let d = rand_in_unit_disc();
+                let z = (1.0 - d.length_squared()).max(0.0).sqrt();
+                tangent * d.x + bitangent * d.y + normal * z
+            }
+            DiffuseMethod::UniformHemisphere => {
+                let d = rand_on_unit_sphere();
+                if d.dot(normal) < 0.0 {
+                    -d
+                } else {
+                    d
+                }
+            }
+            DiffuseMethod::AroundNormal => normal + rand_on_unit_sphere(),
+        };
 
         if is_near_zero(scattered_direction) {
-            scattered_direction = intersection.normal
+            scattered_direction = normal
         }
 
         let scattered = Ray::new(intersection.p, scattered_direction);
 
         return Some((self.albedo, scattered));
     }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::test_util::hit;
+
+    #[test]
+    fn every_method_scatters_into_the_upper_hemisphere() {
+        let methods = [DiffuseMethod::Cosine, DiffuseMethod::UniformHemisphere, DiffuseMethod::AroundNormal];
+
+        for method in methods {
+            for normal in [Vec3::Y, -Vec3::Z, Vec3::new(1.0, 2.0, -3.0).normalize()] {
+                let material = Arc::new(LambertianMaterial::new(Vec3::ONE).with_method(method));
+                let intersection = hit(Vec3::ZERO, normal, material.clone());
+                let r = Ray::new(normal * 2.0, -normal);
+
+                for _ in 0..10_000 {
+                    let (_, scattered) = material.scatter(&r, &intersection).unwrap();
+                    assert!(scattered.direction.dot(normal) >= 0.0, "{:?} scattered below {}", method, normal);
+                }
+            }
+        }
+    }
 }
@@ -1,26 +1,113 @@
+use std::any::Any;
+use std::sync::Arc;
+
 use crate::ray::Ray;
 use crate::scatter::Scatter;
 use crate::surface::SurfaceIntersection;
+use crate::texture::Texture;
 use crate::util::{rand_in_unit_sphere, reflect};
 use glam::*;
 
 pub struct MetalMaterial {
     albedo: Vec3,
     fuzz: f32,
+    // when set, the red channel at the hit replaces `fuzz`
+    roughness_texture: Option<Arc<dyn Texture>>,
 }
 
 impl MetalMaterial {
     pub fn new(albedo: Vec3, fuzz: f32) -> MetalMaterial {
-        MetalMaterial { albedo, fuzz }
+        MetalMaterial { albedo, fuzz, roughness_texture: None }
+    }
+
+    pub fn with_roughness_texture(mut self, roughness_texture: Arc<dyn Texture>) -> MetalMaterial {
+        self.roughness_texture = Some(roughness_texture);
+        return self;
+    }
+
+    pub fn albedo(&self) -> Vec3 {
+        return self.albedo;
+    }
+
+    pub fn fuzz(&self) -> f32 {
+        return self.fuzz;
+    }
+
+    fn fuzz_at(&self, intersection: &SurfaceIntersection) -> f32 {
+        return match &self.roughness_texture {
+            Some(texture) => texture.value(intersection.u, intersection.v, intersection.p).x.clamp(0.0, 1.0),
+            None => self.fuzz,
+        };
     }
 }
 
 impl Scatter for MetalMaterial {
     fn scatter(&self, r: &Ray, intersection: &SurfaceIntersection) -> Option<(Vec3, Ray)> {
-        let reflected_direction = reflect(r.direction, intersection.normal).normalize();
-        let scattered_direction = reflected_direction + rand_in_unit_sphere() * self.fuzz;
+        let reflected_direction = reflect(r.direction.normalize(), intersection.normal);
+        let scattered_direction = reflected_direction + rand_in_unit_sphere() * self.fuzz_at(intersection);
         let scattered = Ray::new(intersection.p, scattered_direction);
 
         return if scattered.direction.dot(intersection.normal) > 0.0 { Some((self.albedo, scattered)) } else { None };
     }
+
+    fn is_specular(&self) -> bool {
+        return self.fuzz == 0.0 && self.roughness_texture.is_none();
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hit;
+
+    // black where u < 0.5, white elsewhere
+    struct Stripe;
+
+    impl Texture for Stripe {
+        fn value(&self, u: f32, _v: f32, _p: Vec3) -> Vec3 {
+            return if u < 0.5 { Vec3::ZERO } else { Vec3::ONE };
+        }
+    }
+
+    #[test]
+    fn roughness_texture_stripe_is_mirror_sharp() {
+        let material = Arc::new(MetalMaterial::new(Vec3::ONE, 0.0).with_roughness_texture(Arc::new(Stripe)));
+        let r = Ray::new(Vec3::new(-1.0, 1.0, 0.0), Vec3::new(1.0, -1.0, 0.0));
+        let mirror = Vec3::new(1.0, 1.0, 0.0).normalize();
+
+        let mut intersection = hit(Vec3::ZERO, Vec3::Y, material.clone());
+
+        intersection.u = 0.25;
+        for _ in 0..100 {
+            let (_, scattered) = material.scatter(&r, &intersection).unwrap();
+            assert!(scattered.direction.normalize().abs_diff_eq(mirror, 1e-6));
+        }
+
+        intersection.u = 0.75;
+        let blurry = (0..100)
+            .filter_map(|_| material.scatter(&r, &intersection))
+            .filter(|(_, scattered)| !scattered.direction.normalize().abs_diff_eq(mirror, 1e-3))
+            .count();
+        assert!(blurry > 50);
+    }
+
+    #[test]
+    fn smooth_metal_mirrors_the_normalized_incident_direction() {
+        let material = Arc::new(MetalMaterial::new(Vec3::ONE, 0.0));
+        let normal = Vec3::new(0.0, 1.0, 1.0).normalize();
+        let intersection = hit(Vec3::ZERO, normal, material.clone());
+
+        let incident = Vec3::new(0.3, -1.0, -0.2).normalize();
+        let mirror = incident - 2.0 * incident.dot(normal) * normal;
+
+        for length in [0.01, 1.0, 250.0] {
+            let r = Ray::new(-incident * length, incident * length);
+            let (_, scattered) = material.scatter(&r, &intersection).unwrap();
+            assert!(scattered.direction.abs_diff_eq(mirror, 1e-6), "{} vs {}", scattered.direction, mirror);
+        }
+    }
 }
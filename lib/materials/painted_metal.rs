@@ -0,0 +1,88 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::ray::Ray;
+use crate::scatter::Scatter;
+use crate::surface::SurfaceIntersection;
+use crate::texture::Texture;
+use crate::util::{is_near_zero, rand_in_unit_sphere, rand_on_unit_sphere, reflect};
+use glam::*;
+use rand::*;
+
+// a textured diffuse base under an untinted specular coat, picking the layer per ray by the coat's fresnel reflectance
+pub struct PaintedMetal {
+    base: Arc<dyn Texture>,
+    // reflectance of the coat at normal incidence
+    specular: f32,
+    fuzz: f32,
+}
+
+impl PaintedMetal {
+    pub fn new(base: Arc<dyn Texture>, specular: f32, fuzz: f32) -> PaintedMetal {
+        PaintedMetal { base, specular: specular.clamp(0.0, 1.0), fuzz }
+    }
+
+    fn fresnel(&self, cos_theta: f32) -> f32 {
+        return self.specular + (1.0 - self.specular) * (1.0 - cos_theta).powi(5);
+    }
+}
+
+impl Scatter for PaintedMetal {
+    fn scatter(&self, r: &Ray, intersection: &SurfaceIntersection) -> Option<(Vec3, Ray)> {
+        let r_direction_norm = r.direction.normalize();
+        let cos_theta = intersection.normal.dot(-r_direction_norm).clamp(0.0, 1.0);
+
+        if self.fresnel(cos_theta) > thread_rng().gen() {
+            let reflected_direction = reflect(r_direction_norm, intersection.normal);
+            let scattered_direction = reflected_direction + rand_in_unit_sphere() * self.fuzz;
+
+            if scattered_direction.dot(intersection.normal) <= 0.0 {
+                return None;
+            }
+
+            return Some((Vec3::ONE, Ray::new(intersection.p, scattered_direction)));
+        }
+
+        let mut scattered_direction = intersection.normal + rand_on_unit_sphere();
+
+        if is_near_zero(scattered_direction) {
+            scattered_direction = intersection.normal
+        }
+
+        let albedo = self.base.value(intersection.u, intersection.v, intersection.p);
+
+        return Some((albedo, Ray::new(intersection.p, scattered_direction)));
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::hit;
+    use crate::texture::SolidColor;
+
+    // mean attenuation of a blue base under a white coat, lit from `incident`
+    fn mean_attenuation(incident: Vec3) -> Vec3 {
+        let material = Arc::new(PaintedMetal::new(Arc::new(SolidColor::new(Vec3::Z)), 0.04, 0.0));
+        let intersection = hit(Vec3::ZERO, Vec3::Y, material.clone());
+        let r = Ray::new(-incident, incident);
+
+        let samples = 10_000;
+        let sum: Vec3 = (0..samples).filter_map(|_| material.scatter(&r, &intersection)).map(|(a, _)| a).sum();
+        return sum / samples as f32;
+    }
+
+    #[test]
+    fn coat_dominates_only_at_grazing_angles() {
+        // the red channel only comes from the coat, the blue one from both layers
+        let normal = mean_attenuation(-Vec3::Y);
+        assert!(normal.x < 0.1 && normal.z > 0.9, "{}", normal);
+
+        let grazing = mean_attenuation(Vec3::new(1.0, -0.01, 0.0));
+        assert!(grazing.x > 0.8, "{}", grazing);
+    }
+}
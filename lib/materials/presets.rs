@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use crate::materials::conductor::ConductorMaterial;
+use crate::materials::dielectric::DielectricMaterial;
+use crate::materials::lambertian::{DiffuseMethod, LambertianMaterial};
+use crate::materials::metal::MetalMaterial;
+use crate::materials::painted_metal::PaintedMetal;
+use crate::scatter::Scatter;
+use crate::texture::SolidColor;
+use glam::*;
+
+// ready-made materials with reasonable parameters
+
+pub fn gold() -> Arc<dyn Scatter> {
+    return Arc::new(ConductorMaterial::gold(0.05));
+}
+
+pub fn copper() -> Arc<dyn Scatter> {
+    return Arc::new(ConductorMaterial::copper(0.1));
+}
+
+pub fn brushed_aluminum() -> Arc<dyn Scatter> {
+    return Arc::new(ConductorMaterial::aluminum(0.3));
+}
+
+pub fn glass() -> Arc<dyn Scatter> {
+    return Arc::new(DielectricMaterial::new(1.5));
+}
+
+pub fn water() -> Arc<dyn Scatter> {
+    return Arc::new(DielectricMaterial::new(1.33));
+}
+
+pub fn diamond() -> Arc<dyn Scatter> {
+    return Arc::new(DielectricMaterial::new(2.42));
+}
+
+// a silvered mirror, which reflects slightly less than all light
+pub fn mirror() -> Arc<dyn Scatter> {
+    return Arc::new(MetalMaterial::new(Vec3::splat(0.95), 0.0));
+}
+
+// fully matte
+pub fn rubber(color: Vec3) -> Arc<dyn Scatter> {
+    return Arc::new(LambertianMaterial::new(color).with_method(DiffuseMethod::Cosine));
+}
+
+// a colored diffuse base under a faint, slightly blurry clear coat
+pub fn plastic(color: Vec3) -> Arc<dyn Scatter> {
+    return Arc::new(PaintedMetal::new(Arc::new(SolidColor::new(color)), 0.04, 0.1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+    use crate::test_util::hit;
+
+    #[test]
+    fn presets_scatter_finite_values() {
+        let presets = [
+            gold(),
+            copper(),
+            brushed_aluminum(),
+            glass(),
+            water(),
+            diamond(),
+            mirror(),
+            rubber(Vec3::new(0.8, 0.1, 0.1)),
+            plastic(Vec3::new(0.1, 0.1, 0.8)),
+        ];
+
+        let normal = Vec3::Y;
+        let incidents = [-Vec3::Y, Vec3::new(1.0, -1.0, 0.0), Vec3::new(1.0, -1e-4, 0.0)];
+
+        for material in presets {
+            for incident in incidents {
+                let mut intersection = hit(Vec3::ZERO, normal, material.clone());
+
+                // from outside and, for transmissive presets, from inside
+                for facing in [true, false] {
+                    intersection.facing = facing;
+
+                    for _ in 0..100 {
+                        if let Some((attenuation, scattered)) =
+                            material.scatter(&Ray::new(-incident, incident), &intersection)
+                        {
+                            assert!(attenuation.is_finite(), "{} attenuation {}", material.type_name(), attenuation);
+                            assert!(scattered.direction.is_finite(), "{} direction", material.type_name());
+                            assert!(scattered.direction != Vec3::ZERO, "{} direction", material.type_name());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
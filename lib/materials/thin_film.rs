@@ -0,0 +1,101 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::ray::Ray;
+use crate::scatter::Scatter;
+use crate::surface::SurfaceIntersection;
+use glam::*;
+
+// representative wavelengths in nanometers for the red, green and blue channels
+const WAVELENGTHS: Vec3 = Vec3::new(650.0, 510.0, 475.0);
+
+// a thin transparent film over a base material, like a soap bubble or an oil slick, which tints reflected light by
+// the interference between the film's top and bottom surfaces
+pub struct ThinFilmMaterial {
+    base: Arc<dyn Scatter>,
+    // film thickness in nanometers
+    thickness: f32,
+    index_of_refraction: f32,
+}
+
+impl ThinFilmMaterial {
+    pub fn new(base: Arc<dyn Scatter>, thickness: f32, index_of_refraction: f32) -> ThinFilmMaterial {
+        ThinFilmMaterial { base, thickness, index_of_refraction }
+    }
+
+    // reflected intensity per channel for light arriving at `cos_theta` to the normal
+    pub fn interference(&self, cos_theta: f32) -> Vec3 {
+        use std::f32::consts::TAU;
+
+        let sin2_theta = 1.0 - cos_theta * cos_theta;
+        let cos_film = (1.0 - sin2_theta / (self.index_of_refraction * self.index_of_refraction)).max(0.0).sqrt();
+
+        // path difference 2 n d cos(theta_t), with a half wave phase flip on reflection off the denser film
+        let path_difference = 2.0 * self.index_of_refraction * self.thickness * cos_film;
+        let phase = TAU * path_difference / WAVELENGTHS;
+
+        return Vec3::new(0.5 * (1.0 - phase.x.cos()), 0.5 * (1.0 - phase.y.cos()), 0.5 * (1.0 - phase.z.cos()));
+    }
+}
+
+impl Scatter for ThinFilmMaterial {
+    fn scatter(&self, r: &Ray, intersection: &SurfaceIntersection) -> Option<(Vec3, Ray)> {
+        let (attenuation, scattered) = self.base.scatter(r, intersection)?;
+
+        // only light bouncing off the film is tinted, transmitted light passes through
+        if scattered.direction.dot(intersection.normal) <= 0.0 {
+            return Some((attenuation, scattered));
+        }
+
+        let cos_theta = intersection.normal.dot(-r.direction.normalize()).clamp(0.0, 1.0);
+
+        return Some((attenuation * self.interference(cos_theta), scattered));
+    }
+
+    fn is_specular(&self) -> bool {
+        return self.base.is_specular();
+    }
+
+    fn emitted(&self, intersection: &SurfaceIntersection) -> Vec3 {
+        return self.base.emitted(intersection);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::metal::MetalMaterial;
+
+    // thickness whose normal incidence path difference is half of `wavelength`, reflecting it at full intensity
+    fn half_wave_film(wavelength: f32) -> ThinFilmMaterial {
+        const IOR: f32 = 1.33;
+        let base = Arc::new(MetalMaterial::new(Vec3::ONE, 0.0));
+        return ThinFilmMaterial::new(base, wavelength / (4.0 * IOR), IOR);
+    }
+
+    #[test]
+    fn film_thickness_picks_the_dominant_wavelength() {
+        let reddish = half_wave_film(WAVELENGTHS.x).interference(1.0);
+        assert!((reddish.x - 1.0).abs() < 1e-4);
+        assert!(reddish.x > reddish.y && reddish.x > reddish.z, "{}", reddish);
+
+        let bluish = half_wave_film(WAVELENGTHS.z).interference(1.0);
+        assert!((bluish.z - 1.0).abs() < 1e-4);
+        assert!(bluish.z > bluish.x && bluish.z > bluish.y, "{}", bluish);
+    }
+
+    #[test]
+    fn tilting_shortens_the_path_difference() {
+        // moving away from normal incidence shifts a red film's peak toward shorter wavelengths
+        let film = half_wave_film(WAVELENGTHS.x);
+        let normal = film.interference(1.0);
+        let tilted = film.interference(0.5);
+
+        assert!(tilted.x < normal.x);
+        assert!(tilted.z > normal.z);
+    }
+}
@@ -0,0 +1,74 @@
+use glam::*;
+use rand::*;
+
+use crate::ray::Ray;
+use crate::util::build_onb;
+
+// a homogeneous participating medium filling the whole scene
+#[derive(Clone, Copy)]
+pub struct MediumParams {
+    pub scattering: Vec3,
+    pub absorption: Vec3,
+    // henyey-greenstein anisotropy, negative for back scattering and positive for forward scattering
+    pub g: f32,
+    // distance from the ray origin at which escaping rays leave the medium and see the environment
+    pub extent: f32,
+}
+
+pub enum MediumEvent {
+    // the ray scattered in the medium at `t` before reaching the surface
+    Scattered { t: f32, weight: Vec3 },
+    // the ray reached the surface (or environment) unscattered
+    Transmitted { weight: Vec3 },
+}
+
+impl MediumParams {
+    pub fn new(scattering: Vec3, absorption: Vec3, g: f32, extent: f32) -> MediumParams {
+        MediumParams { scattering, absorption, g: g.clamp(-0.999, 0.999), extent }
+    }
+
+    // samples a free-flight distance along `r` using the channel-averaged extinction, weighting the result so the
+    // estimate stays unbiased per channel
+    pub fn sample(&self, r: &Ray, t_max: f32) -> MediumEvent {
+        let extinction = self.scattering + self.absorption;
+        let sigma = (extinction.x + extinction.y + extinction.z) / 3.0;
+
+        if sigma <= 0.0 {
+            return MediumEvent::Transmitted { weight: Vec3::ONE };
+        }
+
+        let length = r.direction.length();
+        let distance_max = (t_max * length).min(self.extent);
+        let distance = -(1.0 - thread_rng().gen::<f32>()).ln() / sigma;
+
+        return if distance < distance_max {
+            let weight = self.scattering / sigma * (-(extinction - sigma) * distance).exp();
+            MediumEvent::Scattered { t: distance / length, weight }
+        } else {
+            MediumEvent::Transmitted { weight: (-(extinction - sigma) * distance_max).exp() }
+        };
+    }
+
+    // samples a scattered direction from the henyey-greenstein phase function around the direction of travel
+    pub fn sample_phase(&self, direction: Vec3) -> Vec3 {
+        let mut rng = thread_rng();
+        let (xi1, xi2): (f32, f32) = (rng.gen(), rng.gen());
+
+        let g = self.g;
+
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * xi1
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi1);
+            ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+        };
+
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = std::f32::consts::TAU * xi2;
+
+        let w = direction.normalize();
+        let (tangent, bitangent) = build_onb(w);
+
+        return tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + w * cos_theta;
+    }
+}
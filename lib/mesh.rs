@@ -0,0 +1,72 @@
+use std::io::{self, Write};
+
+use glam::*;
+
+// an indexed triangle list, used to hand geometry to external tools
+pub struct TriangleMesh {
+    pub vertices: Vec<Vec3>,
+    pub faces: Vec<[u32; 3]>,
+}
+
+impl TriangleMesh {
+    // a latitude/longitude sphere with `rings` bands from pole to pole and twice as many segments around
+    pub fn uv_sphere(center: Vec3, radius: f32, rings: u32) -> TriangleMesh {
+        use std::f32::consts::PI;
+
+        let rings = rings.max(2);
+        let segments = rings * 2;
+
+        let mut vertices = Vec::with_capacity((2 + (rings - 1) * segments) as usize);
+        let mut faces = Vec::with_capacity((2 * segments * (rings - 1)) as usize);
+
+        vertices.push(center + Vec3::Y * radius);
+
+        for i in 1..rings {
+            let theta = PI * i as f32 / rings as f32;
+            for j in 0..segments {
+                let phi = 2.0 * PI * j as f32 / segments as f32;
+                let direction = Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+                vertices.push(center + direction * radius);
+            }
+        }
+
+        vertices.push(center - Vec3::Y * radius);
+
+        let bottom = vertices.len() as u32 - 1;
+        let ring_start = |i: u32| 1 + i * segments;
+
+        for j in 0..segments {
+            let next = (j + 1) % segments;
+
+            faces.push([0, ring_start(0) + next, ring_start(0) + j]);
+
+            for i in 0..rings - 2 {
+                let (a, b) = (ring_start(i), ring_start(i + 1));
+                faces.push([a + j, a + next, b + next]);
+                faces.push([a + j, b + next, b + j]);
+            }
+
+            let last = ring_start(rings - 2);
+            faces.push([bottom, last + j, last + next]);
+        }
+
+        return TriangleMesh { vertices, faces };
+    }
+
+    // writes the mesh as a wavefront obj object; obj indices are global and 1-based, so `index_offset` is the number
+    // of vertices already written to `w`
+    pub fn write_obj(&self, w: &mut impl Write, name: &str, index_offset: u32) -> io::Result<()> {
+        writeln!(w, "o {}", name)?;
+
+        for v in &self.vertices {
+            writeln!(w, "v {} {} {}", v.x, v.y, v.z)?;
+        }
+
+        for face in &self.faces {
+            let [a, b, c] = face.map(|i| i + index_offset + 1);
+            writeln!(w, "f {} {} {}", a, b, c)?;
+        }
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,368 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use glam::*;
+
+use crate::util::luminance;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputColorSpace {
+    LinearSrgb,
+    // ACES AP1 primaries, D60 white point
+    AcesCg,
+}
+
+impl OutputColorSpace {
+    // converts a linear rec. 709 / srgb color into this color space
+    pub fn convert(&self, color: Vec3) -> Vec3 {
+        match self {
+            OutputColorSpace::LinearSrgb => color,
+            OutputColorSpace::AcesCg => {
+                // rec. 709 to ap1 with bradford chromatic adaptation from d65 to d60
+                let m = Mat3::from_cols_array(&[
+                    0.6130974, 0.3395231, 0.0473795, //
+                    0.0701937, 0.9163539, 0.0134524, //
+                    0.0206156, 0.1095698, 0.8698147, //
+                ])
+                .transpose();
+                m * color
+            }
+        }
+    }
+}
+
+// writes linear colors, top row first, as an 8-bit gamma encoded ppm
+pub fn write_ppm(path: &Path, size: UVec2, colors: &[Vec3]) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+
+    write_ppm_header(&mut w, size)?;
+
+    for color in colors {
+        write_ppm_color(&mut w, *color)?;
+    }
+
+    return w.flush();
+}
+
+pub fn write_ppm_header(w: &mut impl Write, size: UVec2) -> io::Result<()> {
+    writeln!(w, "P3")?;
+    writeln!(w, "{} {}", size.x, size.y)?;
+    writeln!(w, "255")
+}
+
+pub fn write_ppm_color(w: &mut impl Write, color: Vec3) -> io::Result<()> {
+    writeln!(w, "{}", format_color(color))
+}
+
+fn format_color(color: Vec3) -> String {
+    let [r, g, b] = to_rgb8(color);
+    return format!("{} {} {}", r, g, b);
+}
+
+// encodes a linear color for display with a gamma of 2, clamping anything outside the displayable range; renderer
+// output stays linear HDR and only the writers call this
+pub fn to_rgb8(color: Vec3) -> [u8; 3] {
+    let encoded = color.max(Vec3::ZERO).powf(0.5).min(Vec3::splat(0.999)) * 256.0;
+    return [encoded.x as u8, encoded.y as u8, encoded.z as u8];
+}
+
+// scales a linear HDR buffer so its geometric mean luminance lands on `target_luminance`, to be applied before tone
+// mapping when the right exposure for a scene isn't known up front
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoExposure {
+    pub target_luminance: f32,
+}
+
+impl AutoExposure {
+    pub fn new(target_luminance: f32) -> AutoExposure {
+        return AutoExposure { target_luminance };
+    }
+
+    pub fn apply(&self, pixels: &mut [Vec3]) {
+        if pixels.is_empty() {
+            return;
+        }
+
+        // the small offset keeps black pixels from sending the log average to zero
+        const DELTA: f64 = 1e-4;
+
+        let log_sum: f64 = pixels.iter().map(|&c| (DELTA + luminance(c).max(0.0) as f64).ln()).sum();
+        let mean = (log_sum / pixels.len() as f64).exp() - DELTA;
+
+        if !mean.is_finite() || mean <= 0.0 {
+            return;
+        }
+
+        let scale = (self.target_luminance as f64 / mean) as f32;
+
+        for pixel in pixels.iter_mut() {
+            *pixel *= scale;
+        }
+    }
+}
+
+// writes linear colors with alpha, top row first, as an 8-bit binary pam with gamma encoded color and linear alpha;
+// pam stands in for png, which would need an encoder dependency
+pub fn write_rgba_pam(path: &Path, size: UVec2, pixels: &[Vec4]) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+
+    writeln!(w, "P7")?;
+    writeln!(w, "WIDTH {}", size.x)?;
+    writeln!(w, "HEIGHT {}", size.y)?;
+    writeln!(w, "DEPTH 4")?;
+    writeln!(w, "MAXVAL 255")?;
+    writeln!(w, "TUPLTYPE RGB_ALPHA")?;
+    writeln!(w, "ENDHDR")?;
+
+    for pixel in pixels {
+        let [r, g, b] = to_rgb8(pixel.truncate());
+        let a = (pixel.w.clamp(0.0, 1.0) * 255.0).round() as u8;
+        w.write_all(&[r, g, b, a])?;
+    }
+
+    return w.flush();
+}
+
+// writes an id buffer, top row first, as a 16-bit binary pgm; ids above 65535 are clamped
+pub fn write_id_pgm(path: &Path, size: UVec2, ids: &[u32]) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+
+    writeln!(w, "P5")?;
+    writeln!(w, "{} {}", size.x, size.y)?;
+    writeln!(w, "65535")?;
+
+    for &id in ids {
+        w.write_all(&(id.min(u16::MAX as u32) as u16).to_be_bytes())?;
+    }
+
+    return w.flush();
+}
+
+// combines the two eyes of a stereo render into a red/cyan anaglyph, taking red from the left eye and green and blue
+// from the right
+pub fn anaglyph(left: &[Vec3], right: &[Vec3]) -> Vec<Vec3> {
+    return left.iter().zip(right).map(|(l, r)| Vec3::new(l.x, r.y, r.z)).collect();
+}
+
+// adds a blurred copy of the pixels brighter than `threshold` back onto the image, operating on the linear HDR buffer
+pub fn apply_bloom(pixels: &mut [Vec3], size: UVec2, threshold: f32, radius: u32, intensity: f32) {
+    let (w, h) = (size.x as usize, size.y as usize);
+
+    let bright: Vec<Vec3> = pixels.iter().map(|&c| if luminance(c) > threshold { c } else { Vec3::ZERO }).collect();
+
+    let kernel = gaussian_kernel(radius);
+    let r = radius as isize;
+
+    // separable blur, horizontal then vertical pass with edge clamping
+    let mut horizontal = vec![Vec3::ZERO; w * h];
+
+    for y in 0..h {
+        for x in 0..w {
+            let mut sum = Vec3::ZERO;
+            for (k, weight) in kernel.iter().enumerate() {
+                let sx = (x as isize + k as isize - r).clamp(0, w as isize - 1) as usize;
+                sum += bright[y * w + sx] * *weight;
+            }
+            horizontal[y * w + x] = sum;
+        }
+    }
+
+    for y in 0..h {
+        for x in 0..w {
+            let mut sum = Vec3::ZERO;
+            for (k, weight) in kernel.iter().enumerate() {
+                let sy = (y as isize + k as isize - r).clamp(0, h as isize - 1) as usize;
+                sum += horizontal[sy * w + x] * *weight;
+            }
+            pixels[y * w + x] += sum * intensity;
+        }
+    }
+}
+
+fn gaussian_kernel(radius: u32) -> Vec<f32> {
+    let sigma = (radius as f32 * 0.5).max(0.5);
+    let r = radius as i32;
+
+    let kernel: Vec<f32> = (-r..=r).map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp()).collect();
+    let sum: f32 = kernel.iter().sum();
+
+    return kernel.into_iter().map(|k| k / sum).collect();
+}
+
+// prints a downsampled preview of linear colors to the terminal using 24-bit ansi colors, packing two pixels into each
+// character cell with the upper half block
+pub fn print_ansi_preview(pixels: &[Vec3], size: UVec2, target_cols: u32) {
+    let stdout = io::stdout();
+    let _ = write_ansi_preview(&mut stdout.lock(), pixels, size, target_cols);
+}
+
+pub fn write_ansi_preview(w: &mut impl Write, pixels: &[Vec3], size: UVec2, target_cols: u32) -> io::Result<()> {
+    if size.x == 0 || size.y == 0 || target_cols == 0 {
+        return Ok(());
+    }
+
+    let cols = target_cols.min(size.x);
+    let rows = ((cols as f32 * size.y as f32 / size.x as f32).round() as u32).max(1);
+
+    let preview = downsample(pixels, size, UVec2::new(cols, rows));
+
+    for y in (0..rows).step_by(2) {
+        for x in 0..cols {
+            let [r, g, b] = to_rgb8(preview[(y * cols + x) as usize]);
+            write!(w, "\x1b[38;2;{};{};{}m", r, g, b)?;
+
+            // an odd number of rows leaves the last lower half empty
+            if y + 1 < rows {
+                let [r, g, b] = to_rgb8(preview[((y + 1) * cols + x) as usize]);
+                write!(w, "\x1b[48;2;{};{};{}m", r, g, b)?;
+            } else {
+                write!(w, "\x1b[49m")?;
+            }
+
+            write!(w, "\u{2580}")?;
+        }
+        writeln!(w, "\x1b[0m")?;
+    }
+
+    return Ok(());
+}
+
+// box-filters pixels (top row first) down to `target` dimensions
+fn downsample(pixels: &[Vec3], size: UVec2, target: UVec2) -> Vec<Vec3> {
+    let mut result = Vec::with_capacity((target.x * target.y) as usize);
+
+    for ty in 0..target.y {
+        let y0 = ty * size.y / target.y;
+        let y1 = ((ty + 1) * size.y / target.y).max(y0 + 1);
+
+        for tx in 0..target.x {
+            let x0 = tx * size.x / target.x;
+            let x1 = ((tx + 1) * size.x / target.x).max(x0 + 1);
+
+            let mut sum = Vec3::ZERO;
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    sum += pixels[(y * size.x + x) as usize];
+                }
+            }
+
+            result.push(sum / ((x1 - x0) * (y1 - y0)) as f32);
+        }
+    }
+
+    return result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_ppm_to_invalid_path_is_err() {
+        let path = Path::new("/nonexistent-directory/image.ppm");
+        assert!(write_ppm(path, UVec2::new(1, 1), &[Vec3::ONE]).is_err());
+    }
+
+    #[test]
+    fn bloom_spreads_a_bright_pixel_into_its_neighbors() {
+        let size = UVec2::new(9, 9);
+        let mut pixels = vec![Vec3::ZERO; 81];
+        pixels[4 * 9 + 4] = Vec3::splat(10.0);
+
+        apply_bloom(&mut pixels, size, 1.0, 2, 1.0);
+
+        assert!(pixels[4 * 9 + 5].x > 0.0);
+        assert!(pixels[3 * 9 + 4].x > 0.0);
+        assert_eq!(pixels[0], Vec3::ZERO);
+    }
+
+    #[test]
+    fn bloom_leaves_a_dim_image_unchanged() {
+        let size = UVec2::new(9, 9);
+        let mut pixels = vec![Vec3::splat(0.5); 81];
+
+        apply_bloom(&mut pixels, size, 1.0, 2, 1.0);
+
+        assert!(pixels.iter().all(|&c| c == Vec3::splat(0.5)));
+    }
+
+    #[test]
+    fn pure_red_converts_to_the_ap1_red_column() {
+        let ap1 = OutputColorSpace::AcesCg.convert(Vec3::X);
+        assert!(ap1.abs_diff_eq(Vec3::new(0.6130974, 0.0701937, 0.0206156), 1e-6));
+
+        // white stays white after the chromatic adaptation
+        assert!(OutputColorSpace::AcesCg.convert(Vec3::ONE).abs_diff_eq(Vec3::ONE, 1e-4));
+    }
+
+    #[test]
+    fn ansi_preview_packs_two_rows_per_line() {
+        let count_lines = |size: UVec2| {
+            let pixels = vec![Vec3::splat(0.5); (size.x * size.y) as usize];
+            let mut out = Vec::new();
+            write_ansi_preview(&mut out, &pixels, size, size.x).unwrap();
+            return String::from_utf8(out).unwrap().lines().count();
+        };
+
+        assert_eq!(count_lines(UVec2::new(4, 4)), 2);
+        // an odd height rounds up to a final half-filled line
+        assert_eq!(count_lines(UVec2::new(4, 5)), 3);
+        assert_eq!(count_lines(UVec2::new(4, 1)), 1);
+    }
+
+    #[test]
+    fn auto_exposure_brightens_dim_and_darkens_bright_images() {
+        let exposure = AutoExposure::new(0.18);
+
+        let mut dim = vec![Vec3::splat(0.01), Vec3::splat(0.02), Vec3::splat(0.04)];
+        exposure.apply(&mut dim);
+        assert!(dim[0].x > 0.01);
+
+        let mut bright = vec![Vec3::splat(5.0), Vec3::splat(10.0), Vec3::splat(20.0)];
+        exposure.apply(&mut bright);
+        assert!(bright[2].x < 20.0);
+
+        // a uniform image lands on the target exactly
+        let mut uniform = vec![Vec3::splat(3.0); 4];
+        exposure.apply(&mut uniform);
+        assert!((luminance(uniform[0]) - 0.18).abs() < 1e-3);
+    }
+
+    #[test]
+    fn anaglyph_takes_red_from_the_left_eye() {
+        let left = [Vec3::new(0.9, 0.1, 0.2)];
+        let right = [Vec3::new(0.3, 0.6, 0.7)];
+
+        assert_eq!(anaglyph(&left, &right), vec![Vec3::new(0.9, 0.6, 0.7)]);
+    }
+
+    #[test]
+    fn rgba_pam_round_trips() {
+        let size = UVec2::new(3, 1);
+        let pixels = [Vec4::new(0.25, 1.0, 0.0, 1.0), Vec4::new(0.5, 0.1, 0.8, 0.4), Vec4::new(4.0, 0.0, 0.0, 0.0)];
+
+        let path = std::env::temp_dir().join(format!("rgba_pam_test_{}.pam", std::process::id()));
+        write_rgba_pam(&path, size, &pixels).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let header = "P7\nWIDTH 3\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n";
+        assert!(bytes.starts_with(header.as_bytes()));
+
+        // undo the gamma and quantization, which brings back everything in the displayable range
+        let decoded: Vec<Vec4> = bytes[header.len()..]
+            .chunks_exact(4)
+            .map(|p| {
+                let color = (Vec3::new(p[0] as f32, p[1] as f32, p[2] as f32) / 256.0).powf(2.0);
+                return color.extend(p[3] as f32 / 255.0);
+            })
+            .collect();
+
+        assert_eq!(decoded.len(), 3);
+        assert!(decoded[0].abs_diff_eq(pixels[0], 0.01), "{}", decoded[0]);
+        assert!(decoded[1].abs_diff_eq(pixels[1], 0.01), "{}", decoded[1]);
+        // hdr values clamp to white
+        assert!(decoded[2].abs_diff_eq(Vec4::new(1.0, 0.0, 0.0, 0.0), 0.01), "{}", decoded[2]);
+    }
+}
@@ -0,0 +1,23 @@
+// receives render progress, so a front end can show it with whatever ui library it likes; methods are called from the
+// render threads
+pub trait ProgressReporter: Send + Sync {
+    // called once before rendering starts, with the number of tiles
+    fn set_total(&self, total: u64);
+
+    // called as tiles complete
+    fn inc(&self, delta: u64);
+
+    fn finish(&self);
+}
+
+// reports nothing
+#[derive(Default)]
+pub struct NoProgress;
+
+impl ProgressReporter for NoProgress {
+    fn set_total(&self, _total: u64) {}
+
+    fn inc(&self, _delta: u64) {}
+
+    fn finish(&self) {}
+}
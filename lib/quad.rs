@@ -0,0 +1,120 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::ray::Ray;
+use crate::scatter::Scatter;
+use crate::surface::{Surface, SurfaceIntersection};
+use crate::validation::SurfaceIssue;
+use glam::*;
+
+// a parallelogram with corner `q` and edges `u` and `v`; the front face is the side `u.cross(v)` points to
+pub struct Quad {
+    q: Vec3,
+    u: Vec3,
+    v: Vec3,
+    material: Arc<dyn Scatter>,
+}
+
+impl Quad {
+    pub fn new(q: Vec3, u: Vec3, v: Vec3, material: Arc<dyn Scatter>) -> Quad {
+        return Quad { q, u, v, material };
+    }
+
+    // the six outward-facing sides of a box of `size` centered on `center`, rotated about its center
+    pub fn cuboid(center: Vec3, size: Vec3, rotation: Quat, material: Arc<dyn Scatter>) -> Vec<Quad> {
+        let dx = rotation * Vec3::new(size.x, 0.0, 0.0);
+        let dy = rotation * Vec3::new(0.0, size.y, 0.0);
+        let dz = rotation * Vec3::new(0.0, 0.0, size.z);
+
+        let min = center - (dx + dy + dz) * 0.5;
+        let max = center + (dx + dy + dz) * 0.5;
+
+        return vec![
+            Quad::new(min, dz, dy, material.clone()),
+            Quad::new(max, -dy, -dz, material.clone()),
+            Quad::new(min, dx, dz, material.clone()),
+            Quad::new(max, -dz, -dx, material.clone()),
+            Quad::new(min, dy, dx, material.clone()),
+            Quad::new(max, -dx, -dy, material),
+        ];
+    }
+}
+
+impl Surface for Quad {
+    fn raycast(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<SurfaceIntersection> {
+        let n = self.u.cross(self.v);
+        let outward_normal = n.normalize();
+
+        let denominator = outward_normal.dot(r.direction);
+
+        // parallel to the plane
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.q - r.origin).dot(outward_normal) / denominator;
+
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        let p = r.at(t);
+
+        // planar coordinates of the hit in the basis of the two edges
+        let w = n / n.length_squared();
+        let offset = p - self.q;
+        let alpha = w.dot(offset.cross(self.v));
+        let beta = w.dot(self.u.cross(offset));
+
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let facing = denominator < 0.0;
+        let normal = if facing { outward_normal } else { -outward_normal };
+
+        return Some(SurfaceIntersection {
+            p,
+            t,
+            facing,
+            normal,
+            material: self.material.clone(),
+            u: alpha,
+            v: beta,
+            dpdu: self.u,
+            dpdv: self.v,
+        });
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let corners = [self.q, self.q + self.u, self.q + self.v, self.q + self.u + self.v];
+        let min = corners.iter().fold(Vec3::splat(f32::INFINITY), |acc, &c| acc.min(c));
+        let max = corners.iter().fold(Vec3::splat(f32::NEG_INFINITY), |acc, &c| acc.max(c));
+        return Some(Aabb::new(min, max));
+    }
+
+    fn area(&self) -> Option<f32> {
+        return Some(self.u.cross(self.v).length());
+    }
+
+    fn validate(&self) -> Option<SurfaceIssue> {
+        if !self.q.is_finite() || !self.u.is_finite() || !self.v.is_finite() {
+            return Some(SurfaceIssue::NonFinite);
+        }
+
+        if self.u.cross(self.v).length() < f32::EPSILON {
+            return Some(SurfaceIssue::Degenerate);
+        }
+
+        return None;
+    }
+
+    fn material(&self) -> Option<&Arc<dyn Scatter>> {
+        return Some(&self.material);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
@@ -1,5 +1,6 @@
 use glam::*;
 
+#[derive(Clone, Copy, Debug)]
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
@@ -14,3 +15,17 @@ impl Ray {
         self.origin + self.direction * t
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_output_shows_origin_and_direction() {
+        let r = Ray::new(Vec3::new(1.0, 2.0, 3.0), Vec3::new(-4.0, 5.5, 0.25));
+        let debug = format!("{:?}", r);
+
+        assert!(debug.contains(&format!("{:?}", r.origin)), "{}", debug);
+        assert!(debug.contains(&format!("{:?}", r.direction)), "{}", debug);
+    }
+}
@@ -0,0 +1,735 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use glam::*;
+use rand::Rng;
+use rayon::prelude::*;
+
+use crate::camera::Camera;
+use crate::environment::{Environment, GradientSky};
+use crate::filter::PixelFilter;
+use crate::medium::{MediumEvent, MediumParams};
+use crate::output::OutputColorSpace;
+use crate::progress::{NoProgress, ProgressReporter};
+use crate::ray::Ray;
+use crate::surface::{SurfaceIntersection, ALL_LAYERS};
+use crate::util::{luminance, offset_ray_origin};
+use crate::world::World;
+
+// color left in tiles that were never rendered because the time budget ran out
+pub const UNRENDERED_COLOR: Vec3 = Vec3::new(1.0, 0.0, 1.0);
+
+pub struct Renderer {
+    pub size: UVec2,
+    pub samples_per_pixel: u32,
+    pub depth: u32,
+    pub filter: PixelFilter,
+    pub tile_size: u32,
+    pub tile_order: TileOrder,
+    pub time_budget: Option<Duration>,
+    pub layer_mask: u32,
+    pub t_min: f32,
+    // upper bound on the ray parameter of any hit, which also bounds how far ray marched surfaces march
+    pub max_ray_distance: f32,
+    // caps the brightest channel of the radiance carried by secondary rays, trading energy for fewer fireflies;
+    // primary rays are never clamped, so directly visible lights keep their brightness
+    pub indirect_clamp: Option<f32>,
+    pub environment: Box<dyn Environment>,
+    pub color_space: OutputColorSpace,
+    pub global_medium: Option<MediumParams>,
+    // when set, every sample is taken at `frame_jitter(frame_index)` from the pixel center instead of a random
+    // offset, so an external accumulator can combine frames of an animation into an anti-aliased result; all samples
+    // of a frame land on the same spot, leaving the filter nothing to weigh, so this requires `samples_per_pixel` 1
+    pub frame_index: Option<u32>,
+    pub progress: Box<dyn ProgressReporter>,
+}
+
+// order in which tiles are handed to the thread pool, the final image does not depend on it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TileOrder {
+    // rows from the top of the image
+    Scanline,
+    // z-order curve over the tile grid
+    Morton,
+    // rings outwards from the center of the image
+    Spiral,
+}
+
+pub struct RenderReport {
+    pub elapsed: Duration,
+    pub total_rays: u64,
+    pub total_samples: u64,
+    pub rays_per_second: f64,
+}
+
+// per-pixel results gathered alongside the color
+#[derive(Clone, Copy)]
+struct Pixel {
+    color: Vec3,
+    // fraction of primary rays that hit a surface
+    alpha: f32,
+    // sample variance of the luminance
+    variance: f32,
+}
+
+#[derive(Clone, Copy)]
+struct Tile {
+    min: UVec2,
+    max: UVec2,
+    // position in the tile grid, counted from the top left
+    cell: UVec2,
+}
+
+impl Renderer {
+    pub fn new(size: UVec2, samples_per_pixel: u32, depth: u32) -> Renderer {
+        return Renderer {
+            size,
+            samples_per_pixel,
+            depth,
+            filter: PixelFilter::Box,
+            tile_size: 16,
+            tile_order: TileOrder::Scanline,
+            time_budget: None,
+            layer_mask: ALL_LAYERS,
+            t_min: 0.001,
+            max_ray_distance: f32::MAX,
+            indirect_clamp: None,
+            environment: Box::new(GradientSky::default()),
+            color_space: OutputColorSpace::LinearSrgb,
+            global_medium: None,
+            frame_index: None,
+            progress: Box::new(NoProgress),
+        };
+    }
+
+    // the averaged linear color of every pixel, top row first
+    pub fn render(&self, world: &World, camera: &Camera) -> Vec<Vec3> {
+        return self.render_with_stats(world, camera).0;
+    }
+
+    pub fn render_with_stats(&self, world: &World, camera: &Camera) -> (Vec<Vec3>, RenderReport) {
+        let (pixels, report) = self.render_pixels(world, camera);
+        return (pixels.into_iter().map(|pixel| pixel.color).collect(), report);
+    }
+
+    // like `render`, with the pixel coverage in the alpha channel: 1 where every primary ray hit a surface and 0
+    // where all of them escaped to the environment
+    pub fn render_rgba(&self, world: &World, camera: &Camera) -> Vec<Vec4> {
+        let (pixels, _) = self.render_pixels(world, camera);
+        return pixels.into_iter().map(|pixel| pixel.color.extend(pixel.alpha)).collect();
+    }
+
+    // like `render`, along with a grayscale noise map: the per-pixel sample variance of the luminance, normalized so
+    // the noisiest pixel is 1
+    pub fn render_with_variance(&self, world: &World, camera: &Camera) -> (Vec<Vec3>, Vec<f32>) {
+        let (pixels, _) = self.render_pixels(world, camera);
+
+        let max_variance = pixels.iter().map(|pixel| pixel.variance).fold(0.0, f32::max);
+        let scale = if max_variance > 0.0 { 1.0 / max_variance } else { 0.0 };
+
+        return pixels.into_iter().map(|pixel| (pixel.color, pixel.variance * scale)).unzip();
+    }
+
+    // an object id pass, top row first: the `object_id` of the surface seen through each pixel center, or its index
+    // in the world plus one if it has none, and 0 where the primary ray escapes to the environment; rays ignore the
+    // aperture so object edges stay sharp
+    pub fn render_object_ids(&self, world: &World, camera: &Camera) -> Vec<u32> {
+        return (0..self.size.x * self.size.y)
+            .into_par_iter()
+            .map(|i| {
+                let (x, y) = (i % self.size.x, self.size.y - 1 - i / self.size.x);
+                let u = (x as f32 + 0.5) / (self.size.x - 1) as f32;
+                let v = (y as f32 + 0.5) / (self.size.y - 1) as f32;
+                let r = camera.create_pinhole_ray(u, v);
+
+                return match world.raycast_indexed(&r, self.t_min, self.max_ray_distance, self.layer_mask) {
+                    Some((index, _)) => world.surfaces[index].object_id().unwrap_or(index as u32 + 1),
+                    None => 0,
+                };
+            })
+            .collect();
+    }
+
+    fn render_pixels(&self, world: &World, camera: &Camera) -> (Vec<Pixel>, RenderReport) {
+        assert!(
+            self.frame_index.is_none() || self.samples_per_pixel == 1,
+            "frame jitter takes one sample per pixel per frame, got {} samples per pixel",
+            self.samples_per_pixel
+        );
+
+        let start = Instant::now();
+
+        let total_rays = AtomicU64::new(0);
+        let total_samples = AtomicU64::new(0);
+
+        // tiles are rendered in parallel, but each worker claims the next tile from a shared counter so they are
+        // started in `tile_order`; once the budget is exceeded no new tile is started, but tiles already in flight run
+        // to completion
+        let tiles = self.tiles();
+        let next_tile = AtomicUsize::new(0);
+        self.progress.set_total(tiles.len() as u64);
+
+        let rendered: Vec<(Tile, Option<Vec<Pixel>>)> = (0..tiles.len())
+            .into_par_iter()
+            .map(|_| {
+                let tile = tiles[next_tile.fetch_add(1, Ordering::Relaxed)];
+
+                if let Some(budget) = self.time_budget {
+                    if start.elapsed() > budget {
+                        return (tile, None);
+                    }
+                }
+
+                let mut rays = 0;
+                let pixels = self.render_tile(world, camera, &tile, &mut rays);
+
+                total_rays.fetch_add(rays, Ordering::Relaxed);
+                total_samples.fetch_add(pixels.len() as u64 * self.samples_per_pixel as u64, Ordering::Relaxed);
+                self.progress.inc(1);
+
+                return (tile, Some(pixels));
+            })
+            .collect();
+
+        self.progress.finish();
+
+        let unrendered = Pixel { color: UNRENDERED_COLOR, alpha: 0.0, variance: 0.0 };
+        let mut image = vec![unrendered; (self.size.x * self.size.y) as usize];
+
+        for (tile, pixels) in rendered {
+            if let Some(pixels) = pixels {
+                let mut pixels = pixels.into_iter();
+                for y in tile.min.y..tile.max.y {
+                    for x in tile.min.x..tile.max.x {
+                        let pixel = pixels.next().unwrap();
+                        let color = self.color_space.convert(pixel.color);
+                        image[self.index(UVec2::new(x, y))] = Pixel { color, ..pixel };
+                    }
+                }
+            }
+        }
+
+        let elapsed = start.elapsed();
+        let total_rays = total_rays.into_inner();
+
+        let report = RenderReport {
+            elapsed,
+            total_rays,
+            total_samples: total_samples.into_inner(),
+            rays_per_second: total_rays as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        };
+
+        return (image, report);
+    }
+
+    fn tiles(&self) -> Vec<Tile> {
+        let mut tiles = Vec::new();
+        let tile_size = self.tile_size.max(1);
+
+        let grid = (self.size + UVec2::splat(tile_size - 1)) / tile_size;
+
+        for y in (0..self.size.y).step_by(tile_size as usize) {
+            for x in (0..self.size.x).step_by(tile_size as usize) {
+                let min = UVec2::new(x, y);
+                let max = (min + UVec2::splat(tile_size)).min(self.size);
+                let cell = UVec2::new(x / tile_size, grid.y - 1 - y / tile_size);
+                tiles.push(Tile { min, max, cell });
+            }
+        }
+
+        match self.tile_order {
+            TileOrder::Scanline => tiles.sort_by_key(|tile| (tile.cell.y, tile.cell.x)),
+            TileOrder::Morton => tiles.sort_by_key(|tile| morton_code(tile.cell)),
+            TileOrder::Spiral => {
+                let center = grid.as_vec2() * 0.5;
+                let key = |tile: &Tile| {
+                    let d = tile.cell.as_vec2() + Vec2::splat(0.5) - center;
+                    let ring = d.abs().max_element().ceil() as u32;
+                    (ring, d.y.atan2(d.x))
+                };
+                tiles.sort_by(|a, b| {
+                    let (ring_a, angle_a) = key(a);
+                    let (ring_b, angle_b) = key(b);
+                    ring_a.cmp(&ring_b).then(angle_a.total_cmp(&angle_b))
+                });
+            }
+        }
+
+        return tiles;
+    }
+
+    fn render_tile(&self, world: &World, camera: &Camera, tile: &Tile, rays: &mut u64) -> Vec<Pixel> {
+        let mut pixels = Vec::with_capacity(((tile.max.x - tile.min.x) * (tile.max.y - tile.min.y)) as usize);
+
+        for y in tile.min.y..tile.max.y {
+            for x in tile.min.x..tile.max.x {
+                pixels.push(self.sample_pixel(world, camera, UVec2::new(x, y), rays));
+            }
+        }
+
+        return pixels;
+    }
+
+    // pixel coordinates have y pointing up, the image buffer stores the top row first
+    fn index(&self, p: UVec2) -> usize {
+        return ((self.size.y - 1 - p.y) * self.size.x + p.x) as usize;
+    }
+
+    fn sample_pixel(&self, world: &World, camera: &Camera, p: UVec2, rays: &mut u64) -> Pixel {
+        // accumulate in f64 so high sample counts don't lose precision
+        let mut result = DVec3::ZERO;
+        let mut coverage = 0.0f64;
+        let mut weight_sum = 0.0f64;
+
+        // welford's running mean and sum of squared deviations
+        let mut count = 0.0f64;
+        let mut mean = 0.0f64;
+        let mut m2 = 0.0f64;
+
+        let radius = self.filter.radius();
+
+        // random multisampling, weighted by the reconstruction filter
+        for _ in 0..self.samples_per_pixel {
+            let offset = match self.frame_index {
+                Some(frame_index) => frame_jitter(frame_index),
+                None => {
+                    let mut rng = rand::thread_rng();
+                    Vec2::new(rng.gen_range(-radius..radius), rng.gen_range(-radius..radius))
+                }
+            };
+            let u = (p.x as f32 + 0.5 + offset.x) / (self.size.x - 1) as f32;
+            let v = (p.y as f32 + 0.5 + offset.y) / (self.size.y - 1) as f32;
+            let r = camera.create_ray(u, v);
+            let weight = self.filter.weight(offset);
+
+            if self.depth > 0 {
+                *rays += 1;
+                let hit = world.raycast_masked(&r, self.t_min, self.max_ray_distance, self.layer_mask);
+
+                if hit.is_some() {
+                    coverage += weight as f64;
+                }
+
+                let sample = self.shade(world, &r, hit, self.depth, rays);
+                result += weight as f64 * sample.as_dvec3();
+
+                let y = luminance(sample) as f64;
+                count += 1.0;
+                let delta = y - mean;
+                mean += delta / count;
+                m2 += delta * (y - mean);
+            }
+
+            weight_sum += weight as f64;
+        }
+
+        if weight_sum <= 0.0 {
+            return Pixel { color: Vec3::ZERO, alpha: 0.0, variance: 0.0 };
+        }
+
+        let variance = if count > 1.0 { (m2 / (count - 1.0)) as f32 } else { 0.0 };
+
+        return Pixel { color: (result / weight_sum).as_vec3(), alpha: (coverage / weight_sum) as f32, variance };
+    }
+
+    fn raycast(&self, world: &World, ray: &Ray, depth: u32, rays: &mut u64) -> Vec3 {
+        if depth == 0 {
+            return Vec3::ZERO;
+        }
+
+        *rays += 1;
+
+        let hit = world.raycast_masked(ray, self.t_min, self.max_ray_distance, self.layer_mask);
+
+        let radiance = self.shade(world, ray, hit, depth, rays);
+
+        // only secondary rays come through here, primary rays are shaded directly by `sample_pixel`
+        if let Some(limit) = self.indirect_clamp {
+            let peak = radiance.max_element();
+            if peak > limit {
+                return radiance * (limit / peak);
+            }
+        }
+
+        return radiance;
+    }
+
+    // radiance arriving along `ray`, given its nearest surface hit
+    fn shade(&self, world: &World, ray: &Ray, hit: Option<SurfaceIntersection>, depth: u32, rays: &mut u64) -> Vec3 {
+        let mut transmittance = Vec3::ONE;
+
+        if let Some(medium) = &self.global_medium {
+            let t_hit = hit.as_ref().map_or(f32::INFINITY, |intersection| intersection.t);
+
+            match medium.sample(ray, t_hit) {
+                MediumEvent::Scattered { t, weight } => {
+                    let scattered = Ray::new(ray.at(t), medium.sample_phase(ray.direction));
+                    return weight * self.raycast(world, &scattered, depth - 1, rays);
+                }
+                MediumEvent::Transmitted { weight } => transmittance = weight,
+            }
+        }
+
+        let radiance = if let Some(intersection) = hit {
+            // materials may both emit and scatter, so emission is added regardless of the scatter result
+            let emitted = intersection.material.emitted(&intersection);
+
+            if let Some((attenuation, scattered)) = intersection.material.scatter(ray, &intersection) {
+                let origin = offset_ray_origin(intersection.p, intersection.normal, scattered.direction);
+                let scattered = Ray::new(origin, scattered.direction);
+                emitted + attenuation * self.raycast(world, &scattered, depth - 1, rays)
+            } else {
+                emitted
+            }
+        } else {
+            self.environment.background(ray)
+        };
+
+        return transmittance * radiance;
+    }
+}
+
+// sub-pixel offset in -0.5..0.5 for an animation frame, from an 8 frame halton (2, 3) sequence
+pub fn frame_jitter(frame_index: u32) -> Vec2 {
+    let i = frame_index % 8 + 1;
+    return Vec2::new(halton(i, 2), halton(i, 3)) - Vec2::splat(0.5);
+}
+
+fn halton(mut i: u32, base: u32) -> f32 {
+    let mut result = 0.0;
+    let mut f = 1.0;
+
+    while i > 0 {
+        f /= base as f32;
+        result += f * (i % base) as f32;
+        i /= base;
+    }
+
+    return result;
+}
+
+// interleaves the bits of x and y
+fn morton_code(cell: UVec2) -> u64 {
+    let spread = |v: u32| {
+        let mut v = v as u64;
+        v = (v | (v << 16)) & 0x0000_ffff_0000_ffff;
+        v = (v | (v << 8)) & 0x00ff_00ff_00ff_00ff;
+        v = (v | (v << 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+        v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+        v = (v | (v << 1)) & 0x5555_5555_5555_5555;
+        v
+    };
+
+    return spread(cell.x) | (spread(cell.y) << 1);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::layer::Layered;
+    use crate::materials::dielectric::DielectricMaterial;
+    use crate::materials::diffuse_light::DiffuseLightMaterial;
+    use crate::materials::lambertian::LambertianMaterial;
+    use crate::scatter::Scatter;
+    use crate::sphere::Sphere;
+
+    // looks down -z at a unit sphere at the origin, which fills the whole view
+    fn close_up_camera() -> Camera {
+        return Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y, 10.0, 1.0, 0.0, 5.0);
+    }
+
+    fn uniform_sky(color: Vec3) -> Box<dyn Environment> {
+        return Box::new(GradientSky::new(Vec3::Y, color, color));
+    }
+
+    fn single_sphere(material: Arc<dyn Scatter>) -> World {
+        let mut world = World::new();
+        world.surfaces.push(Box::new(Sphere::new(Vec3::ZERO, 1.0, material)));
+        return world;
+    }
+
+    // glass that also glows
+    struct EmissiveGlass {
+        glass: DielectricMaterial,
+        emit: Vec3,
+    }
+
+    impl Scatter for EmissiveGlass {
+        fn scatter(&self, r: &Ray, intersection: &SurfaceIntersection) -> Option<(Vec3, Ray)> {
+            return self.glass.scatter(r, intersection);
+        }
+
+        fn emitted(&self, _intersection: &SurfaceIntersection) -> Vec3 {
+            return self.emit;
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            return self;
+        }
+    }
+
+    #[test]
+    fn emissive_glass_adds_emission_to_transmitted_light() {
+        let material = Arc::new(EmissiveGlass { glass: DielectricMaterial::new(1.5), emit: Vec3::X });
+        let world = single_sphere(material);
+
+        let mut renderer = Renderer::new(UVec2::new(4, 4), 16, 50);
+        renderer.environment = uniform_sky(Vec3::Z);
+
+        for color in renderer.render(&world, &close_up_camera()) {
+            // red only comes from the emission, blue only from the background seen through the glass
+            assert!(color.x >= 1.0, "missing emission: {}", color);
+            assert!(color.z > 0.9, "missing transmitted background: {}", color);
+        }
+    }
+
+    #[test]
+    fn exhausted_time_budget_leaves_tiles_unrendered() {
+        let world = single_sphere(Arc::new(DielectricMaterial::new(1.5)));
+
+        let mut renderer = Renderer::new(UVec2::new(256, 256), 64, 50);
+        renderer.time_budget = Some(Duration::ZERO);
+
+        let image = renderer.render(&world, &close_up_camera());
+        let unrendered = image.iter().filter(|&&color| color == UNRENDERED_COLOR).count();
+
+        assert!(unrendered > image.len() / 2, "only {} of {} pixels unrendered", unrendered, image.len());
+    }
+
+    #[test]
+    fn many_identical_samples_average_back_exactly() {
+        let sky = Vec3::splat(0.1);
+
+        let mut renderer = Renderer::new(UVec2::new(2, 2), 100_000, 1);
+        renderer.environment = uniform_sky(sky);
+
+        // summing 0.1 a hundred thousand times in f32 drifts well away from 0.1
+        for color in renderer.render(&World::new(), &close_up_camera()) {
+            assert_eq!(color, sky);
+        }
+    }
+
+    #[test]
+    fn report_counts_every_sample() {
+        let world = single_sphere(Arc::new(DielectricMaterial::new(1.5)));
+
+        // a size that doesn't divide into whole tiles
+        let renderer = Renderer::new(UVec2::new(20, 12), 3, 4);
+        let (_, report) = renderer.render_with_stats(&world, &close_up_camera());
+
+        assert_eq!(report.total_samples, 20 * 12 * 3);
+        assert!(report.total_rays >= report.total_samples);
+    }
+
+    #[test]
+    fn denser_fog_hides_a_distant_light() {
+        let world = single_sphere(Arc::new(DiffuseLightMaterial::new(Vec3::X)));
+
+        let visibility = |scattering: f32| {
+            let mut renderer = Renderer::new(UVec2::new(4, 4), 64, 4);
+            renderer.environment = uniform_sky(Vec3::ZERO);
+            renderer.global_medium = Some(MediumParams::new(Vec3::splat(scattering), Vec3::ZERO, 0.0, 100.0));
+
+            let pixels = renderer.render(&world, &close_up_camera());
+            return pixels.iter().map(|color| color.x).sum::<f32>() / pixels.len() as f32;
+        };
+
+        let (clear, haze, fog) = (visibility(0.0), visibility(0.1), visibility(1.0));
+        assert!(clear > haze && haze > fog, "{} {} {}", clear, haze, fog);
+    }
+
+    #[test]
+    fn alpha_is_coverage() {
+        let world = single_sphere(Arc::new(DielectricMaterial::new(1.5)));
+
+        // a wide view where the sphere only covers the middle of the image
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y, 60.0, 1.0, 0.0, 5.0);
+        let renderer = Renderer::new(UVec2::new(9, 9), 8, 4);
+        let pixels = renderer.render_rgba(&world, &camera);
+
+        assert_eq!(pixels[4 * 9 + 4].w, 1.0);
+        assert_eq!(pixels[0].w, 0.0);
+    }
+
+    #[test]
+    fn morton_order_follows_the_z_curve() {
+        let mut renderer = Renderer::new(UVec2::new(64, 64), 1, 1);
+        renderer.tile_order = TileOrder::Morton;
+
+        let cells: Vec<(u32, u32)> = renderer.tiles().iter().take(8).map(|tile| (tile.cell.x, tile.cell.y)).collect();
+        assert_eq!(cells, [(0, 0), (1, 0), (0, 1), (1, 1), (2, 0), (3, 0), (2, 1), (3, 1)]);
+    }
+
+    #[test]
+    fn every_tile_order_covers_each_tile_once() {
+        for order in [TileOrder::Scanline, TileOrder::Morton, TileOrder::Spiral] {
+            // a size that doesn't divide into whole tiles
+            let mut renderer = Renderer::new(UVec2::new(50, 37), 1, 1);
+            renderer.tile_order = order;
+
+            let mut covered = vec![0; 50 * 37];
+            for tile in renderer.tiles() {
+                for y in tile.min.y..tile.max.y {
+                    for x in tile.min.x..tile.max.x {
+                        covered[renderer.index(UVec2::new(x, y))] += 1;
+                    }
+                }
+            }
+
+            assert!(covered.iter().all(|&count| count == 1), "{:?} misses or repeats pixels", order);
+        }
+    }
+
+    #[test]
+    fn eight_frames_have_distinct_jitter() {
+        let offsets: Vec<Vec2> = (0..8).map(frame_jitter).collect();
+
+        for (i, a) in offsets.iter().enumerate() {
+            assert!(a.cmpge(Vec2::splat(-0.5)).all() && a.cmplt(Vec2::splat(0.5)).all());
+            assert!(offsets[i + 1..].iter().all(|b| a != b), "{} repeats", a);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "one sample per pixel")]
+    fn frame_jitter_rejects_several_samples_per_pixel() {
+        let mut renderer = Renderer::new(UVec2::new(4, 4), 4, 1);
+        renderer.frame_index = Some(0);
+        renderer.render(&World::new(), &close_up_camera());
+    }
+
+    #[test]
+    fn frame_jitter_replaces_the_random_offset() {
+        // the sky varies across the image, so each pixel's color only depends on where its samples land
+        let render = |frame_index| {
+            let mut renderer = Renderer::new(UVec2::new(8, 8), 1, 1);
+            renderer.environment = Box::new(GradientSky::new(Vec3::Y, Vec3::ZERO, Vec3::ONE));
+            renderer.frame_index = frame_index;
+            return renderer.render(&World::new(), &close_up_camera());
+        };
+
+        assert_eq!(render(Some(3)), render(Some(3)));
+        assert_ne!(render(Some(3)), render(Some(4)));
+        assert_ne!(render(None), render(None));
+    }
+
+    #[test]
+    fn silhouette_pixels_are_noisier_than_flat_ones() {
+        let world = single_sphere(Arc::new(DiffuseLightMaterial::new(Vec3::ONE)));
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y, 60.0, 1.0, 0.0, 5.0);
+
+        let mut renderer = Renderer::new(UVec2::new(9, 9), 64, 2);
+        renderer.environment = uniform_sky(Vec3::ZERO);
+        let (colors, variance) = renderer.render_with_variance(&world, &camera);
+
+        // fully on the light and fully on the sky
+        assert_eq!(variance[4 * 9 + 4], 0.0);
+        assert_eq!(variance[0], 0.0);
+
+        // the noisiest pixel straddles the edge of the light
+        let (noisiest, _) = variance.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap();
+        assert!((variance[noisiest] - 1.0).abs() < 1e-5);
+        assert!(colors[noisiest].x > 0.0 && colors[noisiest].x < 1.0, "{}", colors[noisiest]);
+    }
+
+    #[derive(Default)]
+    struct ProgressCounts {
+        total: AtomicU64,
+        completed: AtomicU64,
+        inc_calls: AtomicU64,
+        finished: AtomicU64,
+    }
+
+    struct CountingProgress(Arc<ProgressCounts>);
+
+    impl ProgressReporter for CountingProgress {
+        fn set_total(&self, total: u64) {
+            self.0.total.store(total, Ordering::Relaxed);
+        }
+
+        fn inc(&self, delta: u64) {
+            self.0.completed.fetch_add(delta, Ordering::Relaxed);
+            self.0.inc_calls.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn finish(&self) {
+            self.0.finished.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn progress_is_reported_once_per_tile() {
+        let counts = Arc::new(ProgressCounts::default());
+
+        // 3 x 2 tiles
+        let mut renderer = Renderer::new(UVec2::new(40, 20), 1, 1);
+        renderer.progress = Box::new(CountingProgress(counts.clone()));
+        renderer.render(&World::new(), &close_up_camera());
+
+        assert_eq!(counts.total.load(Ordering::Relaxed), 6);
+        assert_eq!(counts.completed.load(Ordering::Relaxed), 6);
+        assert_eq!(counts.inc_calls.load(Ordering::Relaxed), 6);
+        assert_eq!(counts.finished.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn indirect_clamp_removes_fireflies_but_not_direct_light() {
+        let light: Arc<dyn Scatter> = Arc::new(DiffuseLightMaterial::new(Vec3::splat(500.0)));
+
+        // a diffuse sphere filling the view, lit by a small bright light just above the view
+        let mut world = single_sphere(Arc::new(LambertianMaterial::new(Vec3::splat(0.5))));
+        world.surfaces.push(Box::new(Sphere::new(Vec3::new(0.0, 0.8, 1.8), 0.3, light.clone())));
+
+        let brightest = |clamp: Option<f32>, world: &World| {
+            let mut renderer = Renderer::new(UVec2::new(4, 4), 64, 2);
+            renderer.environment = uniform_sky(Vec3::ZERO);
+            renderer.indirect_clamp = clamp;
+            return renderer.render(world, &close_up_camera()).iter().map(|c| c.max_element()).fold(0.0, f32::max);
+        };
+
+        assert!(brightest(None, &world) > 1.0);
+        assert!(brightest(Some(1.0), &world) <= 0.5 + 1e-5);
+
+        // looking straight at the light, only primary rays are involved
+        assert_eq!(brightest(Some(1.0), &single_sphere(light)), 500.0);
+    }
+
+    #[test]
+    fn linear_buffer_keeps_values_above_one() {
+        let world = single_sphere(Arc::new(DiffuseLightMaterial::new(Vec3::new(4.0, 2.0, 1.5))));
+        let renderer = Renderer::new(UVec2::new(4, 4), 4, 2);
+
+        for color in renderer.render(&world, &close_up_camera()) {
+            assert_eq!(color, Vec3::new(4.0, 2.0, 1.5));
+        }
+    }
+
+    #[test]
+    fn object_ids_separate_two_objects_from_the_background() {
+        let material: Arc<dyn Scatter> = Arc::new(DielectricMaterial::new(1.5));
+        let sphere_at = |x: f32| Box::new(Sphere::new(Vec3::new(x, 0.0, 0.0), 0.5, material.clone()));
+
+        let mut world = World::new();
+        world.surfaces.push(Box::new(Layered::new(sphere_at(-1.0), 1).with_object_id(7)));
+        world.surfaces.push(Box::new(Layered::new(sphere_at(1.0), 1).with_object_id(9)));
+
+        // a wide aperture, which the id pass must ignore
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y, 40.0, 2.0, 1.0, 5.0);
+        let renderer = Renderer::new(UVec2::new(32, 16), 1, 1);
+
+        let first = renderer.render_object_ids(&world, &camera);
+        assert_eq!(first, renderer.render_object_ids(&world, &camera));
+
+        let mut ids = first.clone();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids, vec![0, 7, 9]);
+
+        // the sphere centers project to columns 11 and 20 of the middle row
+        assert_eq!(first[8 * 32 + 11], 7);
+        assert_eq!(first[8 * 32 + 20], 9);
+    }
+}
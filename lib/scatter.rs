@@ -1,7 +1,100 @@
+use std::any::Any;
+
 use crate::ray::Ray;
 use crate::surface::SurfaceIntersection;
 use glam::*;
 
 pub trait Scatter: Send + Sync {
     fn scatter(&self, r: &Ray, intersection: &SurfaceIntersection) -> Option<(Vec3, Ray)>;
+
+    // perfectly specular materials scatter along a single (delta) direction, so light sampling can't be applied
+    fn is_specular(&self) -> bool {
+        return false;
+    }
+
+    fn emitted(&self, _intersection: &SurfaceIntersection) -> Vec3 {
+        return Vec3::ZERO;
+    }
+
+    // lets callers downcast to the concrete material, e.g. to find every `DiffuseLightMaterial` in a scene
+    fn as_any(&self) -> &dyn Any;
+
+    // name of the concrete material type, for debug output
+    fn type_name(&self) -> &'static str {
+        return short_type_name(std::any::type_name::<Self>());
+    }
+}
+
+// strips the module path from a type name
+pub(crate) fn short_type_name(name: &'static str) -> &'static str {
+    return name.rsplit("::").next().unwrap_or(name);
+}
+
+type ScatterFn = Box<dyn Fn(&Ray, &SurfaceIntersection) -> Option<(Vec3, Ray)> + Send + Sync>;
+
+// a material defined by a closure, for prototyping without a dedicated struct
+pub struct FnMaterial {
+    f: ScatterFn,
+}
+
+impl FnMaterial {
+    pub fn new(f: ScatterFn) -> FnMaterial {
+        FnMaterial { f }
+    }
+}
+
+impl Scatter for FnMaterial {
+    fn scatter(&self, r: &Ray, intersection: &SurfaceIntersection) -> Option<(Vec3, Ray)> {
+        return (self.f)(r, intersection);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::materials::dielectric::DielectricMaterial;
+    use crate::materials::lambertian::LambertianMaterial;
+    use crate::materials::metal::MetalMaterial;
+    use crate::test_util::hit;
+    use crate::util::rand_on_unit_sphere;
+
+    #[test]
+    fn only_delta_materials_are_specular() {
+        assert!(DielectricMaterial::new(1.5).is_specular());
+        assert!(!LambertianMaterial::new(Vec3::ONE).is_specular());
+    }
+
+    #[test]
+    fn closure_lambertian_scatters_into_the_hemisphere() {
+        let material = Arc::new(FnMaterial::new(Box::new(|_r, intersection| {
+            let direction = intersection.normal + rand_on_unit_sphere();
+            return Some((Vec3::splat(0.5), Ray::new(intersection.p, direction)));
+        })));
+
+        let normal = Vec3::new(1.0, 1.0, -1.0).normalize();
+        let intersection = hit(Vec3::ZERO, normal, material.clone());
+        let r = Ray::new(normal, -normal);
+
+        for _ in 0..1000 {
+            let (attenuation, scattered) = material.scatter(&r, &intersection).unwrap();
+            assert_eq!(attenuation, Vec3::splat(0.5));
+            assert!(scattered.direction.dot(normal) >= 0.0);
+        }
+    }
+
+    #[test]
+    fn materials_downcast_to_their_concrete_type() {
+        let metal: Arc<dyn Scatter> = Arc::new(MetalMaterial::new(Vec3::new(0.8, 0.6, 0.2), 0.1));
+        let lambertian: Arc<dyn Scatter> = Arc::new(LambertianMaterial::new(Vec3::ONE));
+
+        let downcast = metal.as_any().downcast_ref::<MetalMaterial>().unwrap();
+        assert_eq!(downcast.albedo(), Vec3::new(0.8, 0.6, 0.2));
+        assert!(lambertian.as_any().downcast_ref::<MetalMaterial>().is_none());
+    }
 }
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use glam::*;
+
+use crate::camera::Camera;
+use crate::materials::diffuse_light::DiffuseLightMaterial;
+use crate::materials::lambertian::LambertianMaterial;
+use crate::quad::Quad;
+use crate::world::World;
+
+// the classic 555 unit cornell box with a ceiling light and two rotated boxes, and a square camera looking in through
+// the open side; it is lit only by its light, so render it with a black environment
+pub fn cornell_box() -> (World, Camera) {
+    let red = Arc::new(LambertianMaterial::new(Vec3::new(0.65, 0.05, 0.05)));
+    let white = Arc::new(LambertianMaterial::new(Vec3::new(0.73, 0.73, 0.73)));
+    let green = Arc::new(LambertianMaterial::new(Vec3::new(0.12, 0.45, 0.15)));
+    let light = Arc::new(DiffuseLightMaterial::new(Vec3::splat(15.0)));
+
+    let mut world = World::new();
+
+    // walls
+    world.surfaces.push(Box::new(Quad::new(Vec3::new(555.0, 0.0, 0.0), Vec3::Y * 555.0, Vec3::Z * 555.0, green)));
+    world.surfaces.push(Box::new(Quad::new(Vec3::ZERO, Vec3::Y * 555.0, Vec3::Z * 555.0, red)));
+    world.surfaces.push(Box::new(Quad::new(Vec3::ZERO, Vec3::X * 555.0, Vec3::Z * 555.0, white.clone())));
+    world.surfaces.push(Box::new(Quad::new(Vec3::splat(555.0), -Vec3::X * 555.0, -Vec3::Z * 555.0, white.clone())));
+    world.surfaces.push(Box::new(Quad::new(
+        Vec3::new(0.0, 0.0, 555.0),
+        Vec3::X * 555.0,
+        Vec3::Y * 555.0,
+        white.clone(),
+    )));
+
+    // the light faces down, just below the ceiling
+    world.surfaces.push(Box::new(Quad::new(Vec3::new(343.0, 554.0, 332.0), -Vec3::X * 130.0, -Vec3::Z * 105.0, light)));
+
+    let boxes = [
+        (Vec3::new(347.5, 165.0, 377.5), Vec3::new(165.0, 330.0, 165.0), 15.0f32),
+        (Vec3::new(212.5, 82.5, 147.5), Vec3::splat(165.0), -18.0f32),
+    ];
+
+    for (center, size, angle) in boxes {
+        let rotation = Quat::from_rotation_y(angle.to_radians());
+
+        for side in Quad::cuboid(center, size, rotation, white.clone()) {
+            world.surfaces.push(Box::new(side));
+        }
+    }
+
+    let camera =
+        Camera::new(Vec3::new(278.0, 278.0, -800.0), Vec3::new(278.0, 278.0, 0.0), Vec3::Y, 40.0, 1.0, 0.0, 10.0);
+
+    return (world, camera);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray::Ray;
+    use crate::surface::Surface;
+
+    #[test]
+    fn cornell_box_has_walls_a_light_and_two_boxes() {
+        let (world, _) = cornell_box();
+
+        // five walls, the light and six sides for each box
+        assert_eq!(world.surfaces.len(), 5 + 1 + 2 * 6);
+
+        // looking straight up from the middle of the floor sees the front of the light
+        let hit = world.raycast(&Ray::new(Vec3::new(278.0, 1.0, 278.0), Vec3::Y), 0.001, f32::MAX).unwrap();
+        let light = hit.material.as_any().downcast_ref::<DiffuseLightMaterial>().unwrap();
+
+        assert!(hit.facing);
+        assert!(light.emit().min_element() > 0.0);
+        assert_eq!(hit.material.emitted(&hit), light.emit());
+    }
+}
@@ -0,0 +1,155 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::ray::Ray;
+use crate::scatter::Scatter;
+use crate::surface::{Surface, SurfaceIntersection};
+use glam::*;
+
+const DEFAULT_MAX_STEPS: u32 = 256;
+const HIT_EPSILON: f32 = 1e-4;
+const NORMAL_EPSILON: f32 = 1e-4;
+
+// an implicit surface defined by a signed distance function, intersected by sphere tracing
+pub struct SdfSurface {
+    sdf: Box<dyn Fn(Vec3) -> f32 + Send + Sync>,
+    material: Arc<dyn Scatter>,
+    // sphere tracing gives up and reports a miss after this many steps
+    max_steps: u32,
+}
+
+impl SdfSurface {
+    pub fn new(sdf: Box<dyn Fn(Vec3) -> f32 + Send + Sync>, material: Arc<dyn Scatter>) -> SdfSurface {
+        return SdfSurface { sdf, material, max_steps: DEFAULT_MAX_STEPS };
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u32) -> SdfSurface {
+        self.max_steps = max_steps;
+        return self;
+    }
+
+    pub fn sphere(center: Vec3, radius: f32, material: Arc<dyn Scatter>) -> SdfSurface {
+        return SdfSurface::new(Box::new(move |p| (p - center).length() - radius), material);
+    }
+
+    fn normal(&self, p: Vec3) -> Vec3 {
+        let dx = (self.sdf)(p + Vec3::X * NORMAL_EPSILON) - (self.sdf)(p - Vec3::X * NORMAL_EPSILON);
+        let dy = (self.sdf)(p + Vec3::Y * NORMAL_EPSILON) - (self.sdf)(p - Vec3::Y * NORMAL_EPSILON);
+        let dz = (self.sdf)(p + Vec3::Z * NORMAL_EPSILON) - (self.sdf)(p - Vec3::Z * NORMAL_EPSILON);
+
+        return Vec3::new(dx, dy, dz).normalize();
+    }
+}
+
+impl Surface for SdfSurface {
+    fn raycast(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<SurfaceIntersection> {
+        let inv_length = 1.0 / r.direction.length();
+
+        let mut t = t_min;
+        let mut hit = None;
+
+        for _ in 0..self.max_steps {
+            if t > t_max {
+                break;
+            }
+
+            let d = (self.sdf)(r.at(t));
+
+            if d.abs() < HIT_EPSILON {
+                hit = Some(t);
+                break;
+            }
+
+            // the distance is in world units, the ray parameter is scaled by the direction length
+            t += d.abs() * inv_length;
+        }
+
+        let t = hit?;
+        let p = r.at(t);
+
+        let outward_normal = self.normal(p);
+        let facing = r.direction.dot(outward_normal) < 0.0;
+        let normal = if facing { outward_normal } else { -outward_normal };
+
+        return Some(SurfaceIntersection {
+            p,
+            t,
+            facing,
+            normal,
+            material: self.material.clone(),
+            u: 0.0,
+            v: 0.0,
+            dpdu: Vec3::ZERO,
+            dpdv: Vec3::ZERO,
+        });
+    }
+
+    // an arbitrary distance function has no known extent
+    fn bounding_box(&self) -> Option<Aabb> {
+        return None;
+    }
+
+    fn material(&self) -> Option<&Arc<dyn Scatter>> {
+        return Some(&self.material);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::lambertian::LambertianMaterial;
+    use crate::sphere::Sphere;
+    use crate::util::rand_on_unit_sphere;
+    use crate::world::World;
+
+    fn material() -> Arc<dyn Scatter> {
+        return Arc::new(LambertianMaterial::new(Vec3::ONE));
+    }
+
+    #[test]
+    fn sdf_sphere_matches_the_analytic_sphere() {
+        let center = Vec3::new(1.0, 0.5, -2.0);
+        let sdf = SdfSurface::sphere(center, 1.5, material());
+        let sphere = Sphere::new(center, 1.5, material());
+
+        for _ in 0..200 {
+            let origin = center + rand_on_unit_sphere() * 6.0;
+            let target = center + rand_on_unit_sphere();
+            let r = Ray::new(origin, (target - origin) * 0.5);
+
+            let expected = sphere.raycast(&r, 0.001, f32::MAX).unwrap();
+            let actual = sdf.raycast(&r, 0.001, f32::MAX).unwrap();
+
+            assert!((expected.t - actual.t).abs() < 1e-3, "t {} vs {}", actual.t, expected.t);
+            assert!(expected.normal.dot(actual.normal) > 0.999);
+        }
+    }
+
+    #[test]
+    fn surface_that_is_never_reached_misses() {
+        let r = Ray::new(Vec3::ZERO, Vec3::new(0.0, 0.0, -1.0));
+
+        // never within the hit epsilon, so only the step limit stops the march
+        for distance in [1.0, 1e-3] {
+            let sdf = SdfSurface::new(Box::new(move |_| distance), material()).with_max_steps(1000);
+            assert!(sdf.raycast(&r, 0.001, f32::MAX).is_none());
+        }
+    }
+
+    #[test]
+    fn area_is_unknown_rather_than_zero() {
+        assert_eq!(SdfSurface::sphere(Vec3::ZERO, 1.0, material()).area(), None);
+
+        // an unknown area makes the whole world's area unknown
+        let mut world = World::new();
+        world.surfaces.push(Box::new(Sphere::new(Vec3::ZERO, 1.0, material())));
+        assert!(world.area().is_some());
+        world.surfaces.push(Box::new(SdfSurface::sphere(Vec3::ZERO, 1.0, material())));
+        assert_eq!(world.area(), None);
+    }
+}
@@ -1,9 +1,15 @@
+use std::any::Any;
 use std::sync::Arc;
 
+use crate::aabb::Aabb;
+use crate::mesh::TriangleMesh;
 use crate::ray::Ray;
 use crate::scatter::Scatter;
 use crate::surface::{Surface, SurfaceIntersection};
+use crate::util::build_onb;
+use crate::validation::SurfaceIssue;
 use glam::*;
+use rand::Rng;
 
 pub struct Sphere {
     center: Vec3,
@@ -15,6 +21,98 @@ impl Sphere {
     pub fn new(center: Vec3, radius: f32, material: Arc<dyn Scatter>) -> Sphere {
         return Sphere { center, radius, material };
     }
+
+    pub fn center(&self) -> Vec3 {
+        return self.center;
+    }
+
+    pub fn radius(&self) -> f32 {
+        return self.radius;
+    }
+
+    pub fn material(&self) -> &Arc<dyn Scatter> {
+        return &self.material;
+    }
+
+    // samples a unit direction from `from` uniformly within the cone the sphere subtends, for sampling a spherical
+    // light; returns the direction and its solid angle pdf, or None if `from` is inside the sphere
+    pub fn sample_direction(&self, from: Vec3) -> Option<(Vec3, f32)> {
+        let cos_theta_max = self.cos_theta_max(from)?;
+
+        let mut rng = rand::thread_rng();
+        let (xi1, xi2): (f32, f32) = (rng.gen(), rng.gen());
+
+        let cos_theta = 1.0 - xi1 + xi1 * cos_theta_max;
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = std::f32::consts::TAU * xi2;
+
+        let w = (self.center - from).normalize();
+        let (tangent, bitangent) = build_onb(w);
+
+        let direction = tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + w * cos_theta;
+
+        return Some((direction, cone_pdf(cos_theta_max)));
+    }
+
+    // solid angle pdf of `sample_direction` generating `direction` from `from`
+    pub fn direction_pdf(&self, from: Vec3, direction: Vec3) -> f32 {
+        let cos_theta_max = match self.cos_theta_max(from) {
+            Some(cos_theta_max) => cos_theta_max,
+            None => return 0.0,
+        };
+
+        let w = (self.center - from).normalize();
+
+        if direction.normalize().dot(w) < cos_theta_max {
+            return 0.0;
+        }
+
+        return cone_pdf(cos_theta_max);
+    }
+
+    // cosine of the half angle of the cone the sphere subtends from `from`
+    fn cos_theta_max(&self, from: Vec3) -> Option<f32> {
+        let distance_squared = from.distance_squared(self.center);
+        let radius_squared = self.radius * self.radius;
+
+        if distance_squared <= radius_squared {
+            return None;
+        }
+
+        return Some((1.0 - radius_squared / distance_squared).max(0.0).sqrt());
+    }
+}
+
+fn cone_pdf(cos_theta_max: f32) -> f32 {
+    return 1.0 / (std::f32::consts::TAU * (1.0 - cos_theta_max));
+}
+
+// spherical parameterization u = phi / 2pi, v = theta / pi of a point on the unit sphere
+pub(crate) fn sphere_uv(p: Vec3) -> (f32, f32) {
+    use std::f32::consts::PI;
+
+    let theta = (-p.y).clamp(-1.0, 1.0).acos();
+    let phi = (-p.z).atan2(p.x) + PI;
+
+    return (phi / (2.0 * PI), theta / PI);
+}
+
+// derivatives of the spherical parameterization at `q`, relative to the center of a sphere of `radius`
+pub(crate) fn sphere_derivatives(q: Vec3, radius: f32) -> (Vec3, Vec3) {
+    use std::f32::consts::PI;
+
+    let s = (q.x * q.x + q.z * q.z).sqrt();
+
+    let dpdu = 2.0 * PI * Vec3::new(q.z, 0.0, -q.x);
+
+    let dpdv = if s > f32::EPSILON {
+        PI * Vec3::new(-q.x * q.y / s, s, -q.z * q.y / s)
+    } else {
+        // at the poles the meridian direction is undefined, pick any tangent
+        PI * Vec3::new(radius, 0.0, 0.0)
+    };
+
+    return (dpdu, dpdv);
 }
 
 impl Surface for Sphere {
@@ -37,9 +135,11 @@ impl Surface for Sphere {
 
         let mut root = root_lower;
 
+        // a ray starting inside the sphere has its near root behind the origin, so it can only hit the far wall from
+        // the inside, which gets a normal flipped to face the ray
         if root < t_min || t_max < root {
             root = root_upper;
-            if root < t_max || t_max < root {
+            if root < t_min || t_max < root {
                 return None;
             }
         }
@@ -51,6 +151,158 @@ impl Surface for Sphere {
         let facing = r.direction.dot(outward_normal) < 0.0;
         let normal = if facing { outward_normal } else { -outward_normal };
 
-        return Some(SurfaceIntersection { p, t, facing, normal, material: self.material.clone() });
+        let (u, v) = sphere_uv(outward_normal);
+        let (dpdu, dpdv) = sphere_derivatives(p - self.center, self.radius);
+
+        return Some(SurfaceIntersection { p, t, facing, normal, material: self.material.clone(), u, v, dpdu, dpdv });
+    }
+
+    fn occluded(&self, r: &Ray, t_min: f32, t_max: f32) -> bool {
+        let oc = r.origin - self.center;
+        let a = r.direction.length_squared();
+        let half_b = oc.dot(r.direction);
+        let c = oc.length_squared() - (self.radius * self.radius);
+
+        let discriminant = (half_b * half_b) - (a * c);
+
+        if discriminant < 0.0 {
+            return false;
+        }
+
+        let discriminant_sqrt = discriminant.sqrt();
+
+        let root_lower = (-half_b - discriminant_sqrt) / a;
+
+        if t_min <= root_lower && root_lower <= t_max {
+            return true;
+        }
+
+        let root_upper = (-half_b + discriminant_sqrt) / a;
+
+        return t_min <= root_upper && root_upper <= t_max;
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = Vec3::splat(self.radius.abs());
+        return Some(Aabb::new(self.center - r, self.center + r));
+    }
+
+    fn bounding_sphere(&self) -> Option<(Vec3, f32)> {
+        return Some((self.center, self.radius.abs()));
+    }
+
+    fn area(&self) -> Option<f32> {
+        return Some(4.0 * std::f32::consts::PI * self.radius * self.radius);
+    }
+
+    fn tessellate(&self, detail: u32) -> Option<TriangleMesh> {
+        return Some(TriangleMesh::uv_sphere(self.center, self.radius.abs(), detail));
+    }
+
+    fn validate(&self) -> Option<SurfaceIssue> {
+        if !self.center.is_finite() || !self.radius.is_finite() {
+            return Some(SurfaceIssue::NonFinite);
+        }
+
+        if self.radius.abs() < f32::EPSILON {
+            return Some(SurfaceIssue::Degenerate);
+        }
+
+        return None;
+    }
+
+    fn material(&self) -> Option<&Arc<dyn Scatter>> {
+        return Some(&self.material);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::lambertian::LambertianMaterial;
+    use crate::util::rand_on_unit_sphere;
+
+    fn sphere(center: Vec3, radius: f32) -> Sphere {
+        return Sphere::new(center, radius, Arc::new(LambertianMaterial::new(Vec3::ONE)));
+    }
+
+    #[test]
+    fn derivatives_are_tangent_to_the_sphere() {
+        let s = sphere(Vec3::new(1.0, -2.0, 0.5), 2.0);
+
+        for _ in 0..1000 {
+            let origin = s.center + rand_on_unit_sphere() * 5.0;
+            let r = Ray::new(origin, s.center - origin);
+            let hit = s.raycast(&r, 0.001, f32::MAX).unwrap();
+
+            assert!(hit.dpdu.normalize_or_zero().dot(hit.normal).abs() < 1e-3);
+            assert!(hit.dpdv.normalize_or_zero().dot(hit.normal).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn occluded_agrees_with_raycast() {
+        let s = sphere(Vec3::new(0.5, 0.0, -1.0), 1.5);
+
+        // origins inside and outside, distances that stop short of, inside and past the sphere
+        for i in 0..2000 {
+            let origin = s.center + rand_on_unit_sphere() * (i % 5) as f32;
+            let r = Ray::new(origin, rand_on_unit_sphere());
+            let t_max = (i % 7) as f32 * 0.75;
+
+            assert_eq!(s.occluded(&r, 0.001, t_max), s.raycast(&r, 0.001, t_max).is_some());
+        }
+    }
+
+    #[test]
+    fn unit_sphere_area_is_four_pi() {
+        let area = sphere(Vec3::new(3.0, -1.0, 2.0), 1.0).area().unwrap();
+        assert!((area - 4.0 * std::f32::consts::PI).abs() < 1e-5);
+    }
+
+    #[test]
+    fn ray_from_inside_hits_the_far_wall_from_behind() {
+        let s = sphere(Vec3::new(0.0, 1.0, 0.0), 2.0);
+        let r = Ray::new(Vec3::new(0.0, 1.0, 0.0), Vec3::X);
+
+        let hit = s.raycast(&r, 0.001, f32::MAX).unwrap();
+
+        assert!((hit.t - 2.0).abs() < 1e-5);
+        assert!(!hit.facing);
+        // the normal is flipped to face the ray
+        assert!(hit.normal.abs_diff_eq(-Vec3::X, 1e-5));
+    }
+
+    #[test]
+    fn cone_samples_hit_the_sphere_with_a_normalized_pdf() {
+        let s = sphere(Vec3::new(0.0, 1.0, -3.0), 1.0);
+        let from = Vec3::new(0.5, 0.0, 1.0);
+
+        for _ in 0..1000 {
+            let (direction, pdf) = s.sample_direction(from).unwrap();
+            assert!(s.raycast(&Ray::new(from, direction), 0.001, f32::MAX).is_some());
+            assert_eq!(s.direction_pdf(from, direction), pdf);
+        }
+
+        // the fraction of all directions that hit the sphere is its solid angle over 4 pi, and a uniform pdf over that
+        // solid angle integrates to one
+        const SAMPLES: u32 = 200_000;
+        let hits =
+            (0..SAMPLES).filter(|_| s.raycast(&Ray::new(from, rand_on_unit_sphere()), 0.001, f32::MAX).is_some());
+        let solid_angle = 4.0 * std::f32::consts::PI * hits.count() as f32 / SAMPLES as f32;
+
+        let (_, pdf) = s.sample_direction(from).unwrap();
+        assert!((pdf * solid_angle - 1.0).abs() < 0.05, "pdf {} solid angle {}", pdf, solid_angle);
+        assert_eq!(s.direction_pdf(from, -Vec3::Y), 0.0);
+    }
+
+    #[test]
+    fn bounding_sphere_is_the_sphere_itself() {
+        let s = sphere(Vec3::new(2.0, -1.0, 0.5), 3.0);
+        assert_eq!(s.bounding_sphere(), Some((Vec3::new(2.0, -1.0, 0.5), 3.0)));
     }
 }
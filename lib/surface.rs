@@ -1,11 +1,73 @@
+use std::any::Any;
+use std::fmt;
 use std::sync::Arc;
 
+use crate::aabb::Aabb;
+use crate::mesh::TriangleMesh;
 use crate::ray::Ray;
-use crate::scatter::Scatter;
+use crate::scatter::{short_type_name, Scatter};
+use crate::validation::SurfaceIssue;
 use glam::*;
 
+pub const DEFAULT_LAYER: u32 = 1;
+pub const ALL_LAYERS: u32 = u32::MAX;
+
 pub trait Surface: Send + Sync {
     fn raycast(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<SurfaceIntersection>;
+
+    // whether anything is hit in [t_min, t_max], surfaces can override this to skip building the intersection
+    fn occluded(&self, r: &Ray, t_min: f32, t_max: f32) -> bool {
+        return self.raycast(r, t_min, t_max).is_some();
+    }
+
+    // None if the surface is unbounded
+    fn bounding_box(&self) -> Option<Aabb>;
+
+    // center and radius of a sphere enclosing the surface, by default the one around its bounding box
+    fn bounding_sphere(&self) -> Option<(Vec3, f32)> {
+        let bbox = self.bounding_box()?;
+        return Some(((bbox.min + bbox.max) * 0.5, bbox.size().length() * 0.5));
+    }
+
+    // total surface area, used to turn area-light samples into solid angle pdfs; None if it isn't known, which is
+    // different from a degenerate surface with zero area
+    fn area(&self) -> Option<f32> {
+        return None;
+    }
+
+    // reports parameters that would render as garbage or produce NaNs
+    fn validate(&self) -> Option<SurfaceIssue> {
+        return None;
+    }
+
+    // a triangle approximation for exporting the scene, `detail` is a surface-specific subdivision level; None if
+    // the surface can't be tessellated
+    fn tessellate(&self, _detail: u32) -> Option<TriangleMesh> {
+        return None;
+    }
+
+    // the material of every hit on the surface, None if it has no single material
+    fn material(&self) -> Option<&Arc<dyn Scatter>> {
+        return None;
+    }
+
+    // bitmask of the layers this surface belongs to, used to filter surfaces out of a render
+    fn layer(&self) -> u32 {
+        return DEFAULT_LAYER;
+    }
+
+    // id written to the object id pass, None to fall back to the surface's index in the world
+    fn object_id(&self) -> Option<u32> {
+        return None;
+    }
+
+    // lets callers downcast to the concrete surface, e.g. to export spheres to another renderer
+    fn as_any(&self) -> &dyn Any;
+
+    // name of the concrete surface type, for debug output
+    fn type_name(&self) -> &'static str {
+        return short_type_name(std::any::type_name::<Self>());
+    }
 }
 
 pub struct SurfaceIntersection {
@@ -14,4 +76,25 @@ pub struct SurfaceIntersection {
     pub facing: bool,
     pub material: Arc<dyn Scatter>,
     pub t: f32,
+    pub u: f32,
+    pub v: f32,
+    // partial derivatives of the hit point w.r.t. the surface parameterization, or zero if it has none
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+}
+
+impl fmt::Debug for SurfaceIntersection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SurfaceIntersection")
+            .field("p", &self.p)
+            .field("normal", &self.normal)
+            .field("facing", &self.facing)
+            .field("material", &self.material.type_name())
+            .field("t", &self.t)
+            .field("u", &self.u)
+            .field("v", &self.v)
+            .field("dpdu", &self.dpdu)
+            .field("dpdv", &self.dpdv)
+            .finish()
+    }
 }
@@ -0,0 +1,13 @@
+use std::sync::Arc;
+
+use glam::*;
+
+use crate::scatter::Scatter;
+use crate::surface::SurfaceIntersection;
+
+// a front-facing hit at `p` with a unit `normal`, for driving materials directly
+pub fn hit(p: Vec3, normal: Vec3, material: Arc<dyn Scatter>) -> SurfaceIntersection {
+    let (dpdu, dpdv) = crate::util::build_onb(normal);
+
+    return SurfaceIntersection { p, normal, facing: true, material, t: 1.0, u: 0.5, v: 0.5, dpdu, dpdv };
+}
@@ -0,0 +1,79 @@
+use glam::*;
+
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f32, v: f32, p: Vec3) -> Vec3;
+}
+
+pub struct SolidColor {
+    color: Vec3,
+}
+
+impl SolidColor {
+    pub fn new(color: Vec3) -> SolidColor {
+        SolidColor { color }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f32, _v: f32, _p: Vec3) -> Vec3 {
+        return self.color;
+    }
+}
+
+// maps a scalar computed from the hit (e.g. height) through a piecewise linear color ramp
+pub struct RampTexture {
+    // sorted by position
+    stops: Vec<(f32, Vec3)>,
+    scalar: Box<dyn Fn(f32, f32, Vec3) -> f32 + Send + Sync>,
+}
+
+impl RampTexture {
+    pub fn new(mut stops: Vec<(f32, Vec3)>, scalar: Box<dyn Fn(f32, f32, Vec3) -> f32 + Send + Sync>) -> RampTexture {
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        RampTexture { stops, scalar }
+    }
+}
+
+impl Texture for RampTexture {
+    fn value(&self, u: f32, v: f32, p: Vec3) -> Vec3 {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Vec3::ZERO,
+        };
+
+        let t = (self.scalar)(u, v, p);
+
+        // clamp outside the ramp, and treat nan as below it so the stop search below always finds an interval
+        if t.is_nan() || t <= first.0 {
+            return first.1;
+        }
+
+        if t >= last.0 {
+            return last.1;
+        }
+
+        let i = self.stops.partition_point(|stop| stop.0 <= t);
+        let (t0, c0) = self.stops[i - 1];
+        let (t1, c1) = self.stops[i];
+
+        return Vec3::lerp(c0, c1, (t - t0) / (t1 - t0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn black_to_white(scalar: f32) -> Vec3 {
+        let ramp = RampTexture::new(vec![(1.0, Vec3::ONE), (0.0, Vec3::ZERO)], Box::new(|u, _v, _p| u));
+        return ramp.value(scalar, 0.0, Vec3::ZERO);
+    }
+
+    #[test]
+    fn ramp_interpolates_and_clamps() {
+        assert!(black_to_white(0.5).abs_diff_eq(Vec3::splat(0.5), 1e-6));
+        assert_eq!(black_to_white(-3.0), Vec3::ZERO);
+        assert_eq!(black_to_white(7.0), Vec3::ONE);
+        assert_eq!(black_to_white(f32::NAN), Vec3::ZERO);
+    }
+}
@@ -1,21 +1,46 @@
 use glam::*;
-use rand::thread_rng;
+use rand::{thread_rng, Rng};
 use rand_distr::*;
 
 pub fn is_near_zero(v: Vec3) -> bool {
     return v.abs_diff_eq(Vec3::ZERO, f32::EPSILON);
 }
 
+// relative luminance of a linear rec. 709 color
+pub fn luminance(color: Vec3) -> f32 {
+    return color.dot(Vec3::new(0.2126, 0.7152, 0.0722));
+}
+
+// the samplers below guard against degenerate samples (e.g. from a broken rng) so a zero or NaN vector never
+// propagates into normalize() and corrupts a pixel
+
 pub fn rand_in_unit_disc() -> Vec2 {
-    return Vec2::from(UnitDisc.sample(&mut thread_rng()));
+    return rand_in_unit_disc_with(&mut thread_rng());
+}
+
+pub fn rand_in_unit_disc_with<R: Rng + ?Sized>(rng: &mut R) -> Vec2 {
+    let v = Vec2::from(UnitDisc.sample(rng));
+    return if v.is_finite() { v } else { Vec2::ZERO };
 }
 
 pub fn rand_in_unit_sphere() -> Vec3 {
-    return Vec3::from(UnitBall.sample(&mut thread_rng()));
+    return rand_in_unit_sphere_with(&mut thread_rng());
+}
+
+pub fn rand_in_unit_sphere_with<R: Rng + ?Sized>(rng: &mut R) -> Vec3 {
+    let v = Vec3::from(UnitBall.sample(rng));
+    return if v.is_finite() { v } else { Vec3::ZERO };
 }
 
 pub fn rand_on_unit_sphere() -> Vec3 {
-    return Vec3::from(UnitSphere.sample(&mut thread_rng()));
+    return rand_on_unit_sphere_with(&mut thread_rng());
+}
+
+// projects a point in the unit ball onto the sphere, which is uniform over directions; a point at (or too close to)
+// the center has no direction
+pub fn rand_on_unit_sphere_with<R: Rng + ?Sized>(rng: &mut R) -> Vec3 {
+    let v = Vec3::from(UnitBall.sample(rng));
+    return if v.is_finite() && !is_near_zero(v) { v.normalize() } else { Vec3::Y };
 }
 
 pub fn reflect(v: Vec3, normal: Vec3) -> Vec3 {
@@ -28,3 +53,114 @@ pub fn refract(v: Vec3, normal: Vec3, ratio: f32) -> Vec3 {
     let r_para = (1.0 - r_perp.length_squared()).abs().sqrt() * inv_normal;
     return r_perp + r_para;
 }
+
+// offsets a hit point along the geometric normal so a ray spawned from it does not re-intersect the surface it left;
+// `normal` faces the incoming ray, so rays continuing on the incident side move along it and transmitted rays against
+pub fn offset_ray_origin(p: Vec3, normal: Vec3, direction: Vec3) -> Vec3 {
+    const EPSILON: f32 = 1e-4;
+
+    // scale with the magnitude of the coordinates, since float precision does
+    let offset = normal * EPSILON * (1.0 + p.abs().max_element());
+
+    return if direction.dot(normal) > 0.0 { p + offset } else { p - offset };
+}
+
+// two unit vectors completing an orthonormal basis with the unit vector `n`, using the branchless construction from
+// duff et al. 2017, "building an orthonormal basis, revisited"
+pub fn build_onb(n: Vec3) -> (Vec3, Vec3) {
+    let sign = 1.0f32.copysign(n.z);
+    let a = -1.0 / (sign + n.z);
+    let b = n.x * n.y * a;
+
+    let tangent = Vec3::new(1.0 + sign * n.x * n.x * a, sign * b, -sign * n.x);
+    let bitangent = Vec3::new(b, sign + n.y * n.y * a, -n.y);
+
+    return (tangent, bitangent);
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::RngCore;
+
+    use super::*;
+
+    // always produces the same bits, so every uniform sample lands on the same value
+    struct ConstantRng(u32);
+
+    impl RngCore for ConstantRng {
+        fn next_u32(&mut self) -> u32 {
+            return self.0;
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            return ((self.0 as u64) << 32) | self.0 as u64;
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for chunk in dest.chunks_mut(4) {
+                chunk.copy_from_slice(&self.0.to_le_bytes()[..chunk.len()]);
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            return Ok(());
+        }
+    }
+
+    #[test]
+    fn degenerate_sample_still_gives_a_unit_direction() {
+        // these bits map every coordinate to exactly 0, so the ball sample is the center
+        let mut rng = ConstantRng(0x8000_0000);
+        assert_eq!(rand_in_unit_sphere_with(&mut rng), Vec3::ZERO);
+
+        let v = rand_on_unit_sphere_with(&mut rng);
+        assert!(v.is_finite());
+        assert!((v.length() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn samples_lie_on_the_unit_sphere() {
+        for _ in 0..1000 {
+            assert!((rand_on_unit_sphere().length() - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn offset_origin_prevents_self_intersection_at_grazing_angles() {
+        use std::sync::Arc;
+
+        use crate::materials::lambertian::LambertianMaterial;
+        use crate::ray::Ray;
+        use crate::sphere::Sphere;
+        use crate::surface::Surface;
+
+        // a huge sphere is a plane for all practical purposes, with the float error of a distant surface
+        let ground = Sphere::new(Vec3::new(0.0, -1000.0, 0.0), 1000.0, Arc::new(LambertianMaterial::new(Vec3::ONE)));
+
+        for i in 0..1000 {
+            let start = Vec3::new(i as f32 * 0.01 - 10.0, 0.5, (i % 7) as f32);
+            let r = Ray::new(start, Vec3::new(1.0, -0.1, 0.01));
+            let hit = ground.raycast(&r, 0.001, f32::MAX).unwrap();
+
+            let direction = reflect(r.direction.normalize(), hit.normal);
+            let bounced = Ray::new(offset_ray_origin(hit.p, hit.normal, direction), direction);
+
+            assert!(ground.raycast(&bounced, 0.0, f32::MAX).is_none(), "acne at {}", hit.p);
+        }
+    }
+
+    #[test]
+    fn onb_is_orthonormal_near_the_poles() {
+        for n in [Vec3::Y, -Vec3::Y, Vec3::new(1e-7, 1.0, -1e-7).normalize(), Vec3::new(0.0, -1.0, 1e-8).normalize()] {
+            let (tangent, bitangent) = build_onb(n);
+
+            assert!(tangent.is_finite() && bitangent.is_finite(), "{} gives {} {}", n, tangent, bitangent);
+            assert!((tangent.length() - 1.0).abs() < 1e-5);
+            assert!((bitangent.length() - 1.0).abs() < 1e-5);
+            assert!(tangent.dot(bitangent).abs() < 1e-5);
+            assert!(tangent.dot(n).abs() < 1e-5);
+            assert!(bitangent.dot(n).abs() < 1e-5);
+        }
+    }
+}
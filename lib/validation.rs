@@ -0,0 +1,59 @@
+use std::fmt;
+
+use glam::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SurfaceIssue {
+    // zero radius, area or extent
+    Degenerate,
+    // a position or size is NaN or infinite
+    NonFinite,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SceneWarning {
+    Surface { index: usize, issue: SurfaceIssue },
+    // the camera up vector is (nearly) parallel to the view direction, so the camera basis is undefined
+    CameraUpParallel,
+}
+
+impl fmt::Display for SceneWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneWarning::Surface { index, issue: SurfaceIssue::Degenerate } => {
+                write!(f, "surface {} is degenerate", index)
+            }
+            SceneWarning::Surface { index, issue: SurfaceIssue::NonFinite } => {
+                write!(f, "surface {} has non-finite parameters", index)
+            }
+            SceneWarning::CameraUpParallel => write!(f, "camera up vector is parallel to the view direction"),
+        }
+    }
+}
+
+pub fn validate_camera(origin: Vec3, target: Vec3, up: Vec3) -> Option<SceneWarning> {
+    let forward = (target - origin).normalize_or_zero();
+    let up = up.normalize_or_zero();
+
+    if forward == Vec3::ZERO || up == Vec3::ZERO || forward.cross(up).length_squared() < 1e-6 {
+        return Some(SceneWarning::CameraUpParallel);
+    }
+
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camera_looking_along_up_is_reported() {
+        let origin = Vec3::new(1.0, 2.0, 3.0);
+
+        assert_eq!(validate_camera(origin, origin + Vec3::Y, Vec3::Y), Some(SceneWarning::CameraUpParallel));
+        assert_eq!(validate_camera(origin, origin - Vec3::Y * 5.0, Vec3::Y), Some(SceneWarning::CameraUpParallel));
+        assert_eq!(validate_camera(origin, origin, Vec3::Y), Some(SceneWarning::CameraUpParallel));
+
+        assert_eq!(validate_camera(origin, Vec3::ZERO, Vec3::Y), None);
+    }
+}
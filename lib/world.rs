@@ -1,28 +1,441 @@
+use std::any::Any;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use glam::*;
+use rand::Rng;
+
+use crate::aabb::Aabb;
+use crate::camera::Camera;
+use crate::flat::{flatten_world, FlatScene};
 use crate::ray::Ray;
-use crate::surface::{Surface, SurfaceIntersection};
+use crate::scatter::Scatter;
+use crate::scene;
+use crate::sphere::Sphere;
+use crate::surface::{Surface, SurfaceIntersection, ALL_LAYERS};
+use crate::validation::SceneWarning;
 
+#[derive(Default)]
 pub struct World {
     pub surfaces: Vec<Box<dyn Surface>>,
 }
 
+pub struct SceneStats {
+    pub surface_count: usize,
+    // surfaces whose material emits light when seen from the front
+    pub emissive_count: usize,
+    pub bounding_box: Option<Aabb>,
+}
+
+// rough estimate of the heap memory held by a world; shared materials and textures are not counted, since they can be
+// referenced from many surfaces, nor is anything a surface owns behind its own pointers
+pub struct MemoryReport {
+    pub surface_count: usize,
+    // the surfaces themselves
+    pub geometry_bytes: usize,
+    // the list of boxed surfaces, including spare capacity
+    pub list_bytes: usize,
+}
+
+impl MemoryReport {
+    pub fn total_bytes(&self) -> usize {
+        return self.geometry_bytes + self.list_bytes;
+    }
+}
+
 impl World {
     pub fn new() -> World {
         World { surfaces: Vec::new() }
     }
-}
 
-impl Surface for World {
-    fn raycast(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<SurfaceIntersection> {
+    pub fn with_capacity(capacity: usize) -> World {
+        World { surfaces: Vec::with_capacity(capacity) }
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.surfaces.reserve(additional);
+    }
+
+    // removes all surfaces but keeps the allocation, so a world can be refilled every frame
+    pub fn clear(&mut self) {
+        self.surfaces.clear();
+    }
+
+    // moves all of `other`'s surfaces into this world
+    pub fn extend(&mut self, other: World) {
+        self.surfaces.extend(other.surfaces);
+    }
+
+    pub fn merged(mut a: World, b: World) -> World {
+        a.extend(b);
+        return a;
+    }
+
+    // places one small sphere per `spacing` cell across the xz extent of `bounds`, jittered within its cell and resting
+    // on `bounds.min.y`, skipping any that would come within `exclusion_radius` of an exclusion point
+    pub fn scatter_grid<R: Rng>(
+        &mut self,
+        bounds: Aabb,
+        spacing: f32,
+        exclusions: &[Vec3],
+        exclusion_radius: f32,
+        rng: &mut R,
+        material_picker: impl Fn(&mut R) -> Arc<dyn Scatter>,
+    ) {
+        let radius = spacing * 0.2;
+        let cells = (bounds.size() / spacing).floor().as_ivec3();
+
+        for a in 0..cells.x {
+            for b in 0..cells.z {
+                let center = Vec3::new(
+                    bounds.min.x + (a as f32 + rng.gen_range(0.0..0.9)) * spacing,
+                    bounds.min.y + radius,
+                    bounds.min.z + (b as f32 + rng.gen_range(0.0..0.9)) * spacing,
+                );
+
+                if exclusions.iter().any(|point| point.distance(center) < exclusion_radius) {
+                    continue;
+                }
+
+                let mat = material_picker(rng);
+
+                self.surfaces.push(Box::new(Sphere::new(center, radius, mat)));
+            }
+        }
+    }
+
+    pub fn stats(&self) -> SceneStats {
+        let emissive_count = self.surfaces.iter().filter_map(|obj| obj.material()).filter(|m| is_emissive(m)).count();
+
+        return SceneStats { surface_count: self.surfaces.len(), emissive_count, bounding_box: self.bounding_box() };
+    }
+
+    pub fn memory_report(&self) -> MemoryReport {
+        return MemoryReport {
+            surface_count: self.surfaces.len(),
+            geometry_bytes: self.surfaces.iter().map(|obj| std::mem::size_of_val(obj.as_ref())).sum(),
+            list_bytes: self.surfaces.capacity() * std::mem::size_of::<Box<dyn Surface>>(),
+        };
+    }
+
+    pub fn validate(&self) -> Vec<SceneWarning> {
+        let mut warnings = Vec::new();
+
+        for (index, obj) in self.surfaces.iter().enumerate() {
+            if let Some(issue) = obj.validate() {
+                warnings.push(SceneWarning::Surface { index, issue });
+            }
+        }
+
+        return warnings;
+    }
+
+    // writes every surface that can be tessellated to a wavefront obj file, with spheres split into `detail` rings,
+    // and returns the indices of the surfaces that were skipped
+    pub fn export_obj(&self, path: &Path, detail: u32) -> io::Result<Vec<usize>> {
+        let mut w = BufWriter::new(File::create(path)?);
+        let mut skipped = Vec::new();
+        let mut vertex_count = 0;
+
+        for (index, obj) in self.surfaces.iter().enumerate() {
+            match obj.tessellate(detail) {
+                Some(mesh) => {
+                    mesh.write_obj(&mut w, &format!("surface_{}", index), vertex_count)?;
+                    vertex_count += mesh.vertices.len() as u32;
+                }
+                None => skipped.push(index),
+            }
+        }
+
+        w.flush()?;
+
+        return Ok(skipped);
+    }
+
+    // writes one line per surface with its type, layers and bounds
+    pub fn dump_tree(&self, w: &mut impl Write) -> io::Result<()> {
+        writeln!(w, "World ({} surfaces)", self.surfaces.len())?;
+
+        for (index, obj) in self.surfaces.iter().enumerate() {
+            let bounds = match obj.bounding_box() {
+                Some(bbox) => format!("{} .. {}", bbox.min, bbox.max),
+                None => "unbounded".to_string(),
+            };
+
+            writeln!(w, "  [{}] {} layer {:#x} {}", index, obj.type_name(), obj.layer(), bounds)?;
+        }
+
+        return Ok(());
+    }
+
+    // the cornell box reference scene, with its camera
+    pub fn cornell_box() -> (World, Camera) {
+        return scene::cornell_box();
+    }
+
+    // flattens the spheres of the world into plain arrays, with one material entry per distinct material `Arc`
+    pub fn flatten(&self) -> FlatScene {
+        return flatten_world(self);
+    }
+
+    // like `raycast`, but ignores surfaces with no layer in `layer_mask`
+    pub fn raycast_masked(&self, r: &Ray, t_min: f32, t_max: f32, layer_mask: u32) -> Option<SurfaceIntersection> {
+        return self.raycast_indexed(r, t_min, t_max, layer_mask).map(|(_, intersection)| intersection);
+    }
+
+    // like `occluded`, but ignores surfaces with no layer in `layer_mask`, so shadow rays agree with `raycast_masked`
+    pub fn occluded_masked(&self, r: &Ray, t_min: f32, t_max: f32, layer_mask: u32) -> bool {
+        return self.surfaces.iter().any(|obj| obj.layer() & layer_mask != 0 && obj.occluded(r, t_min, t_max));
+    }
+
+    // like `raycast_masked`, also returning the index of the surface that was hit
+    pub fn raycast_indexed(
+        &self,
+        r: &Ray,
+        t_min: f32,
+        t_max: f32,
+        layer_mask: u32,
+    ) -> Option<(usize, SurfaceIntersection)> {
         let mut result = None;
         let mut t_nearest = t_max;
 
-        for obj in &self.surfaces {
+        for (index, obj) in self.surfaces.iter().enumerate() {
+            if obj.layer() & layer_mask == 0 {
+                continue;
+            }
+
             if let Some(intersection) = obj.raycast(r, t_min, t_nearest) {
                 t_nearest = intersection.t;
-                result = Some(intersection);
+                result = Some((index, intersection));
             }
         }
 
         return result;
     }
 }
+
+// whether `material` emits anything at a front-facing hit; emission that varies across a surface is only probed at
+// the center of its uv range
+fn is_emissive(material: &Arc<dyn Scatter>) -> bool {
+    let probe = SurfaceIntersection {
+        p: Vec3::ZERO,
+        normal: Vec3::Y,
+        facing: true,
+        material: material.clone(),
+        t: 1.0,
+        u: 0.5,
+        v: 0.5,
+        dpdu: Vec3::X,
+        dpdv: Vec3::Z,
+    };
+
+    return material.emitted(&probe) != Vec3::ZERO;
+}
+
+impl Surface for World {
+    fn raycast(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<SurfaceIntersection> {
+        return self.raycast_masked(r, t_min, t_max, ALL_LAYERS);
+    }
+
+    fn occluded(&self, r: &Ray, t_min: f32, t_max: f32) -> bool {
+        return self.occluded_masked(r, t_min, t_max, ALL_LAYERS);
+    }
+
+    // None if the world is empty or contains an unbounded surface
+    fn bounding_box(&self) -> Option<Aabb> {
+        let mut result: Option<Aabb> = None;
+
+        for obj in &self.surfaces {
+            let bbox = obj.bounding_box()?;
+            result = Some(match result {
+                Some(acc) => acc.union(&bbox),
+                None => bbox,
+            });
+        }
+
+        return result;
+    }
+
+    // None if any surface's area is unknown
+    fn area(&self) -> Option<f32> {
+        return self.surfaces.iter().map(|obj| obj.area()).sum();
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        return self;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::capsule::Capsule;
+    use crate::layer::Layered;
+    use crate::materials::diffuse_light::DiffuseLightMaterial;
+    use crate::materials::lambertian::LambertianMaterial;
+    use crate::quad::Quad;
+    use crate::validation::SurfaceIssue;
+
+    fn sphere_at(center: Vec3) -> Box<dyn Surface> {
+        return Box::new(Sphere::new(center, 1.0, Arc::new(LambertianMaterial::new(Vec3::ONE))));
+    }
+
+    fn ray_toward(target: Vec3) -> Ray {
+        return Ray::new(target + Vec3::Z * 10.0, -Vec3::Z);
+    }
+
+    #[test]
+    fn masked_out_layers_are_not_hit() {
+        let mut world = World::new();
+        world.surfaces.push(Box::new(Layered::new(sphere_at(Vec3::ZERO), 0b10)));
+
+        let r = ray_toward(Vec3::ZERO);
+
+        assert!(world.raycast_masked(&r, 0.001, f32::MAX, 0b10).is_some());
+        assert!(world.raycast_masked(&r, 0.001, f32::MAX, !0b10).is_none());
+        assert!(world.raycast(&r, 0.001, f32::MAX).is_some());
+    }
+
+    #[test]
+    fn clear_keeps_capacity_and_empties_the_world() {
+        let mut world = World::with_capacity(8);
+        world.surfaces.push(sphere_at(Vec3::ZERO));
+        let capacity = world.surfaces.capacity();
+
+        world.clear();
+
+        assert_eq!(world.surfaces.len(), 0);
+        assert_eq!(world.surfaces.capacity(), capacity);
+        assert!(world.raycast(&ray_toward(Vec3::ZERO), 0.001, f32::MAX).is_none());
+    }
+
+    #[test]
+    fn validate_reports_degenerate_surfaces_by_index() {
+        let material: Arc<dyn Scatter> = Arc::new(LambertianMaterial::new(Vec3::ONE));
+
+        let mut world = World::new();
+        world.surfaces.push(sphere_at(Vec3::ZERO));
+        world.surfaces.push(Box::new(Sphere::new(Vec3::X, 0.0, material.clone())));
+        world.surfaces.push(Box::new(Quad::new(Vec3::ZERO, Vec3::X, Vec3::X * 2.0, material.clone())));
+        world.surfaces.push(Box::new(Capsule::new(Vec3::ZERO, Vec3::ZERO, 1.0, material)));
+
+        let degenerate = |index| SceneWarning::Surface { index, issue: SurfaceIssue::Degenerate };
+        assert_eq!(world.validate(), vec![degenerate(1), degenerate(2), degenerate(3)]);
+    }
+
+    #[test]
+    fn merged_worlds_keep_every_surface() {
+        let mut a = World::new();
+        a.surfaces.push(sphere_at(Vec3::new(-10.0, 0.0, 0.0)));
+        a.surfaces.push(sphere_at(Vec3::new(-5.0, 0.0, 0.0)));
+
+        let mut b = World::new();
+        for x in [0.0, 5.0, 10.0] {
+            b.surfaces.push(sphere_at(Vec3::new(x, 0.0, 0.0)));
+        }
+
+        let world = World::merged(a, b);
+        assert_eq!(world.surfaces.len(), 5);
+
+        for x in [-10.0, -5.0, 0.0, 5.0, 10.0] {
+            let hit = world.raycast(&ray_toward(Vec3::new(x, 0.0, 0.0)), 0.001, f32::MAX).unwrap();
+            assert!(hit.p.abs_diff_eq(Vec3::new(x, 0.0, 1.0), 1e-4));
+        }
+    }
+
+    #[test]
+    fn scatter_grid_keeps_clear_of_exclusions() {
+        let material: Arc<dyn Scatter> = Arc::new(LambertianMaterial::new(Vec3::ONE));
+        let exclusions = [Vec3::new(0.0, 0.0, 0.0), Vec3::new(4.0, 0.0, -3.0)];
+        let bounds = Aabb::new(Vec3::new(-10.0, 0.0, -10.0), Vec3::new(10.0, 0.0, 10.0));
+
+        let mut world = World::new();
+        let mut rng = StdRng::seed_from_u64(7);
+        world.scatter_grid(bounds, 1.0, &exclusions, 2.5, &mut rng, |_| material.clone());
+
+        assert!(!world.surfaces.is_empty());
+
+        for obj in &world.surfaces {
+            let center = obj.as_any().downcast_ref::<Sphere>().unwrap().center();
+            assert!(exclusions.iter().all(|point| point.distance(center) >= 2.5), "{} is too close", center);
+        }
+    }
+
+    #[test]
+    fn memory_report_sums_surface_sizes() {
+        let material: Arc<dyn Scatter> = Arc::new(LambertianMaterial::new(Vec3::ONE));
+
+        let mut world = World::new();
+        world.surfaces.push(sphere_at(Vec3::ZERO));
+        world.surfaces.push(sphere_at(Vec3::X));
+        world.surfaces.push(Box::new(Quad::new(Vec3::ZERO, Vec3::X, Vec3::Y, material)));
+
+        let report = world.memory_report();
+        assert_eq!(report.surface_count, 3);
+        assert_eq!(report.geometry_bytes, 2 * std::mem::size_of::<Sphere>() + std::mem::size_of::<Quad>());
+    }
+
+    #[test]
+    fn exported_sphere_has_a_vertex_per_ring_segment_plus_poles() {
+        let mut world = World::new();
+        world.surfaces.push(sphere_at(Vec3::ZERO));
+
+        let path = std::env::temp_dir().join(format!("export_obj_test_{}.obj", std::process::id()));
+        let skipped = world.export_obj(&path, 6).unwrap();
+        let obj = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // two poles plus 2 * rings segments on each of the rings - 1 latitudes
+        let rings = 6;
+        assert!(skipped.is_empty());
+        assert_eq!(obj.lines().filter(|line| line.starts_with("v ")).count(), 2 + (rings - 1) * 2 * rings);
+        assert_eq!(obj.lines().filter(|line| line.starts_with("f ")).count(), 2 * 2 * rings * (rings - 1));
+    }
+
+    #[test]
+    fn dump_tree_names_the_wrapped_surface_and_its_layer() {
+        let mut world = World::new();
+        world.surfaces.push(Box::new(Layered::new(sphere_at(Vec3::ZERO), 0b100)));
+
+        let mut out = Vec::new();
+        world.dump_tree(&mut out).unwrap();
+        let dump = String::from_utf8(out).unwrap();
+
+        assert!(dump.starts_with("World (1 surfaces)"));
+        assert!(dump.contains("[0] Sphere layer 0x4"), "{}", dump);
+    }
+
+    #[test]
+    fn masked_out_blockers_do_not_occlude() {
+        let mut world = World::new();
+        world.surfaces.push(Box::new(Layered::new(sphere_at(Vec3::ZERO), 0b10)));
+
+        let r = ray_toward(Vec3::ZERO);
+
+        assert!(world.occluded_masked(&r, 0.001, f32::MAX, 0b10));
+        assert!(!world.occluded_masked(&r, 0.001, f32::MAX, !0b10));
+        assert!(world.occluded(&r, 0.001, f32::MAX));
+    }
+
+    #[test]
+    fn stats_count_emissive_surfaces_through_layers() {
+        let light: Arc<dyn Scatter> = Arc::new(DiffuseLightMaterial::new(Vec3::splat(4.0)));
+
+        let mut world = World::new();
+        world.surfaces.push(sphere_at(Vec3::ZERO));
+        world.surfaces.push(Box::new(Layered::new(Box::new(Sphere::new(Vec3::X * 3.0, 1.0, light)), 0b10)));
+
+        let stats = world.stats();
+        assert_eq!(stats.surface_count, 2);
+        assert_eq!(stats.emissive_count, 1);
+
+        let bbox = stats.bounding_box.unwrap();
+        assert!(bbox.min.abs_diff_eq(Vec3::splat(-1.0), 1e-5));
+        assert!(bbox.max.abs_diff_eq(Vec3::new(4.0, 1.0, 1.0), 1e-5));
+    }
+}
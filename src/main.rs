@@ -1,52 +1,35 @@
-use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+#![allow(clippy::needless_return)]
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use glam::*;
-use rand::Rng;
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
+use raytracing_in_one_weekend::aabb::Aabb;
 use raytracing_in_one_weekend::camera::Camera;
+use raytracing_in_one_weekend::filter::PixelFilter;
 use raytracing_in_one_weekend::materials::dielectric::DielectricMaterial;
 use raytracing_in_one_weekend::materials::lambertian::LambertianMaterial;
 use raytracing_in_one_weekend::materials::metal::MetalMaterial;
-use raytracing_in_one_weekend::ray::Ray;
+use raytracing_in_one_weekend::output::{print_ansi_preview, write_ppm};
+use raytracing_in_one_weekend::renderer::Renderer;
 use raytracing_in_one_weekend::scatter::Scatter;
 use raytracing_in_one_weekend::sphere::Sphere;
-use raytracing_in_one_weekend::surface::Surface;
-use raytracing_in_one_weekend::util::rand_on_unit_sphere;
+use raytracing_in_one_weekend::util::rand_on_unit_sphere_with;
+use raytracing_in_one_weekend::validation::validate_camera;
 use raytracing_in_one_weekend::world::World;
 
-fn background(ray: &Ray) -> Vec3 {
-    const COLOR_T: Vec3 = Vec3::new(0.5, 0.7, 1.0);
-    const COLOR_B: Vec3 = Vec3::new(1.0, 1.0, 1.0);
-
-    let ray_dir_normalized = ray.direction.normalize();
-
-    let t = 0.5 * (ray_dir_normalized.y as f32 + 1.0);
-
-    return Vec3::lerp(COLOR_B, COLOR_T, t);
-}
-
-fn raycast(world: &World, ray: &Ray, depth: u32) -> Vec3 {
-    if depth <= 0 {
-        return Vec3::ZERO;
-    }
-
-    return if let Some(intersection) = world.raycast(&ray, 0.001, f32::MAX) {
-        if let Some((attenuation, scattered)) = intersection.material.scatter(ray, &intersection) {
-            attenuation * raycast(&world, &scattered, depth - 1)
-        } else {
-            Vec3::ZERO
-        }
-    } else {
-        background(&ray)
+// the same seed always places the same small spheres with the same materials
+fn create_world(seed: Option<u64>) -> World {
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
     };
-}
-
-fn create_world() -> World {
-    let mut rng = rand::thread_rng();
     let mut world = World::new();
 
     world.surfaces.push({
@@ -55,28 +38,23 @@ fn create_world() -> World {
         Box::new(obj)
     });
 
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose: f32 = rng.gen();
-
-            let mat = if choose < 0.8 {
-                let albedo = rand_on_unit_sphere() * rand_on_unit_sphere();
-                Arc::new(LambertianMaterial::new(albedo)) as Arc<dyn Scatter>
-            } else if choose < 0.95 {
-                let albedo = Vec3::splat(0.4) + rand_on_unit_sphere() * 0.6;
-                let fuzz = rng.gen_range(0.0..0.5);
-                Arc::new(MetalMaterial::new(albedo, fuzz)) as Arc<dyn Scatter>
-            } else {
-                Arc::new(DielectricMaterial::new(1.5)) as Arc<dyn Scatter>
-            };
+    let grid_bounds = Aabb::new(Vec3::new(-11.0, 0.0, -11.0), Vec3::new(11.0, 0.0, 11.0));
+    let feature_spheres = [Vec3::new(0.0, 1.0, 0.0), Vec3::new(-4.0, 1.0, 0.0), Vec3::new(4.0, 1.0, 0.0)];
 
-            let center = Vec3::new((a as f32) + rng.gen_range(0.0..0.9), 0.2, (b as f32) + rng.gen_range(0.0..0.9));
+    world.scatter_grid(grid_bounds, 1.0, &feature_spheres, 1.2, &mut rng, |rng| {
+        let choose: f32 = rng.gen();
 
-            let obj = Sphere::new(center, 0.2, mat);
-
-            world.surfaces.push(Box::new(obj));
+        if choose < 0.8 {
+            let albedo = rand_on_unit_sphere_with(rng) * rand_on_unit_sphere_with(rng);
+            Arc::new(LambertianMaterial::new(albedo)) as Arc<dyn Scatter>
+        } else if choose < 0.95 {
+            let albedo = Vec3::splat(0.4) + rand_on_unit_sphere_with(rng) * 0.6;
+            let fuzz = rng.gen_range(0.0..0.5);
+            Arc::new(MetalMaterial::new(albedo, fuzz)) as Arc<dyn Scatter>
+        } else {
+            Arc::new(DielectricMaterial::new(1.5)) as Arc<dyn Scatter>
         }
-    }
+    });
 
     world.surfaces.push({
         let mat = Arc::new(DielectricMaterial::new(1.5));
@@ -99,15 +77,95 @@ fn create_world() -> World {
     return world;
 }
 
-fn main() {
+fn print_stats(world: &World) {
+    let stats = world.stats();
+
+    println!("surfaces: {}", stats.surface_count);
+    println!("emissive: {}", stats.emissive_count);
+
+    match stats.bounding_box {
+        Some(bbox) => println!("bounds: {} .. {} (size {})", bbox.min, bbox.max, bbox.size()),
+        None => println!("bounds: unbounded"),
+    }
+}
+
+// render settings that can be overridden from the environment
+struct Settings {
+    width: u32,
+    samples_per_pixel: u32,
+    depth: u32,
+    // seeds the scene layout, pixel samples are always random
+    seed: Option<u64>,
+    output: PathBuf,
+}
+
+// parses the value of setting `name`, None if it isn't set
+fn parse_setting<T: FromStr>(name: &str, value: Option<&str>) -> Result<Option<T>, String> {
+    return match value {
+        Some(value) => match value.trim().parse() {
+            Ok(parsed) => Ok(Some(parsed)),
+            Err(_) => Err(format!("{} must be a non-negative integer, got '{}'", name, value)),
+        },
+        None => Ok(None),
+    };
+}
+
+fn lookup_setting<T: FromStr>(lookup: &dyn Fn(&str) -> Option<OsString>, name: &str) -> Result<Option<T>, String> {
+    let value = lookup(name);
+
+    let value = match &value {
+        Some(value) => Some(value.to_str().ok_or_else(|| format!("{} is not valid unicode", name))?),
+        None => None,
+    };
+
+    return parse_setting(name, value);
+}
+
+// builds the settings from `lookup`, which returns the value of a variable or None if it isn't set
+fn parse_settings(lookup: &dyn Fn(&str) -> Option<OsString>) -> Result<Settings, String> {
+    let settings = Settings {
+        width: lookup_setting(lookup, "RT_WIDTH")?.unwrap_or(400),
+        samples_per_pixel: lookup_setting(lookup, "RT_SAMPLES")?.unwrap_or(20),
+        depth: lookup_setting(lookup, "RT_DEPTH")?.unwrap_or(5),
+        seed: lookup_setting(lookup, "RT_SEED")?,
+        output: lookup("RT_OUTPUT").map_or_else(|| PathBuf::from("image.ppm"), PathBuf::from),
+    };
+
+    if settings.width < 2 {
+        return Err(format!("RT_WIDTH must be at least 2, got {}", settings.width));
+    }
+
+    if settings.samples_per_pixel == 0 {
+        return Err("RT_SAMPLES must be at least 1".to_string());
+    }
+
+    return Ok(settings);
+}
+
+fn read_settings() -> Result<Settings, String> {
+    return parse_settings(&|name| std::env::var_os(name));
+}
+
+fn main() -> ExitCode {
     const ASPECT_RATIO: f32 = 3.0 / 2.0;
-    const IMAGE_W: u32 = 400;
-    const IMAGE_H: u32 = (IMAGE_W as f32 / ASPECT_RATIO) as u32;
+    const FILTER: PixelFilter = PixelFilter::Box;
 
-    const SAMPLES_PER_PIXEL: u32 = 20;
-    const DEPTH: u32 = 5;
+    let settings = match read_settings() {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let size = UVec2::new(settings.width, ((settings.width as f32 / ASPECT_RATIO) as u32).max(2));
 
-    let world = create_world();
+    let world = create_world(settings.seed);
+
+    if std::env::args().any(|arg| arg == "--stats") {
+        print_stats(&world);
+        return ExitCode::SUCCESS;
+    }
 
     let camera_origin = Vec3::new(13.0, 2.0, 3.0);
     let camera_target = Vec3::new(0.0, 0.0, 0.0);
@@ -115,6 +173,13 @@ fn main() {
     let camera_focal_length = 10.0;
     let camera_aperture = 0.1;
 
+    let mut warnings = world.validate();
+    warnings.extend(validate_camera(camera_origin, camera_target, Vec3::Y));
+
+    for warning in &warnings {
+        eprintln!("warning: {}", warning);
+    }
+
     let camera = Camera::new(
         camera_origin,
         camera_target,
@@ -125,64 +190,93 @@ fn main() {
         camera_focal_length,
     );
 
-    let path = Path::new("image.ppm");
-    let mut w = File::create(&path).unwrap();
+    let mut renderer = Renderer::new(size, settings.samples_per_pixel, settings.depth);
+    renderer.filter = FILTER;
 
-    write_ppm_header(&mut w, UVec2::new(IMAGE_W, IMAGE_H));
+    let colors = renderer.render(&world, &camera);
 
-    // render all pixels in parallel
+    if std::env::args().any(|arg| arg == "--preview") {
+        print_ansi_preview(&colors, size, 80);
+    }
 
-    let mut pixels = Vec::new();
+    let path = settings.output.as_path();
 
-    for y in (0..IMAGE_H).rev() {
-        for x in 0..IMAGE_W {
-            pixels.push(UVec2::new(x, y));
-        }
+    // colors are linear, the writer applies gamma
+    if let Err(err) = write_ppm(path, size, &colors) {
+        eprintln!("error: could not write {}: {}", path.display(), err);
+        return ExitCode::FAILURE;
     }
 
-    let render = |pixel: UVec2| -> Vec3 {
-        return sample_pixel(&world, &camera, pixel, UVec2::new(IMAGE_W, IMAGE_H), SAMPLES_PER_PIXEL, DEPTH);
-    };
+    return ExitCode::SUCCESS;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
 
-    let colors: Vec<Vec3> = pixels.into_par_iter().map(render).collect();
+    #[test]
+    fn stats_count_every_surface_of_the_demo_scene() {
+        let world = create_world(None);
+        let stats = world.stats();
 
-    for color in colors {
-        // multisample averaging and gamma correction
-        let r = (color.x / SAMPLES_PER_PIXEL as f32).sqrt().clamp(0.0, 0.999);
-        let g = (color.y / SAMPLES_PER_PIXEL as f32).sqrt().clamp(0.0, 0.999);
-        let b = (color.z / SAMPLES_PER_PIXEL as f32).sqrt().clamp(0.0, 0.999);
-        write_ppm_color(&mut w, Vec3::new(r, g, b));
+        assert_eq!(stats.surface_count, world.surfaces.len());
+        assert_eq!(stats.emissive_count, 0);
+
+        // the ground sphere dominates the bounds, the three large spheres reach highest
+        let bbox = stats.bounding_box.unwrap();
+        assert!(bbox.min.abs_diff_eq(Vec3::new(-1000.0, -2000.0, -1000.0), 1e-3), "{}", bbox.min);
+        assert!(bbox.max.abs_diff_eq(Vec3::new(1000.0, 2.0, 1000.0), 1e-3), "{}", bbox.max);
     }
-}
 
-fn sample_pixel(world: &World, camera: &Camera, p: UVec2, size: UVec2, samples: u32, depth: u32) -> Vec3 {
-    let mut result = Vec3::new(0.0, 0.0, 0.0);
+    #[test]
+    fn same_seed_builds_the_same_scene() {
+        let dump = |seed| {
+            let mut out = Vec::new();
+            create_world(Some(seed)).dump_tree(&mut out).unwrap();
+            return String::from_utf8(out).unwrap();
+        };
 
-    // random multisampling
-    for _ in 0..samples {
-        let mut rng = rand::thread_rng();
-        let u = (p.x as f32 + rng.gen_range(0.0..1.0)) / (size.x - 1) as f32;
-        let v = (p.y as f32 + rng.gen_range(0.0..1.0)) / (size.y - 1) as f32;
-        let r = camera.create_ray(u, v);
-        result += raycast(&world, &r, depth);
+        assert_eq!(dump(7), dump(7));
+        assert_ne!(dump(7), dump(8));
     }
 
-    return result;
-}
+    #[test]
+    fn malformed_settings_are_described() {
+        let err = parse_setting::<u32>("RT_WIDTH", Some("wide")).unwrap_err();
+        assert!(err.contains("RT_WIDTH") && err.contains("'wide'"), "{}", err);
 
-pub fn write_ppm_header(w: &mut File, size: UVec2) {
-    writeln!(w, "P3").unwrap();
-    writeln!(w, "{} {}", size.x, size.y).unwrap();
-    writeln!(w, "255").unwrap();
-}
+        assert_eq!(parse_setting::<u32>("RT_WIDTH", Some(" 640 ")), Ok(Some(640)));
+        assert!(parse_setting::<u32>("RT_DEPTH", Some("-3")).is_err());
 
-pub fn write_ppm_color(w: &mut File, color: Vec3) {
-    writeln!(w, "{}", format_color(color)).unwrap()
-}
+        let lookup = |name: &str| (name == "RT_SAMPLES").then(|| OsString::from("lots"));
+        let err = parse_settings(&lookup).err().unwrap();
+        assert!(err.contains("RT_SAMPLES") && err.contains("'lots'"), "{}", err);
+    }
+
+    #[test]
+    fn unset_settings_use_the_defaults() {
+        let settings = parse_settings(&|_| None).unwrap();
+
+        assert_eq!(settings.width, 400);
+        assert_eq!(settings.samples_per_pixel, 20);
+        assert_eq!(settings.depth, 5);
+        assert_eq!(settings.seed, None);
+        assert_eq!(settings.output, PathBuf::from("image.ppm"));
+    }
+
+    #[test]
+    fn demo_scene_flattens_with_one_entry_per_material() {
+        let world = create_world(Some(3));
+        let scene = world.flatten();
 
-fn format_color(color: Vec3) -> String {
-    let r = (color.x * 255.999).clamp(0.0, 256.0) as i32;
-    let g = (color.y * 255.999).clamp(0.0, 256.0) as i32;
-    let b = (color.z * 255.999).clamp(0.0, 256.0) as i32;
-    return format!("{} {} {}", r, g, b);
+        let distinct: HashSet<*const ()> =
+            world.surfaces.iter().map(|obj| Arc::as_ptr(obj.material().unwrap()) as *const ()).collect();
+
+        assert!(scene.unsupported.is_empty());
+        assert_eq!(scene.spheres.len(), world.surfaces.len());
+        assert_eq!(scene.materials.len(), distinct.len());
+        assert!(scene.spheres.iter().all(|sphere| (sphere.material as usize) < scene.materials.len()));
+    }
 }